@@ -1,5 +1,5 @@
 use chrono::NaiveDateTime;
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Parser)]
@@ -38,6 +38,7 @@ pub enum SubCmd {
     Restore(RestoreArgs),
     Remove(RemoveArgs),
     Fix(FixArgs),
+    Doctor(DoctorArgs),
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -57,11 +58,41 @@ pub struct PutArgs {
     /// Does nothing, exists for compatibility with rm
     #[arg(short, long)]
     pub directory: bool,
+
+    /// When run through sudo or pkexec, use the trash of root instead of the one of the invoking user
+    #[arg(long)]
+    pub as_root: bool,
+}
+
+/// Options to select the trashcan to operate on
+#[derive(Debug, Clone, Args)]
+pub struct TrashDirArgs {
+    /// Only use the trash at this directory (containing `info` and `files`), instead of all trashes on the system
+    #[arg(long, value_name = "DIR")]
+    pub trash_dir: Option<PathBuf>,
+
+    /// Use the trashes of all users on the system instead of your own (requires root)
+    #[arg(long, conflicts_with_all = ["trash_dir", "user"])]
+    pub all_users: bool,
+
+    /// Use the trashes of this user instead of your own (requires root)
+    #[arg(long, value_name = "NAME", conflicts_with = "trash_dir")]
+    pub user: Option<String>,
+}
+
+impl TrashDirArgs {
+    /// Whether the trashes of other users are used, in which case files are attributed to their owner
+    pub fn other_users(&self) -> bool {
+        self.all_users || self.user.is_some()
+    }
 }
 
 /// List trashed files
 #[derive(Debug, Clone, Parser)]
 pub struct ListArgs {
+    #[command(flatten)]
+    pub trash: TrashDirArgs,
+
     /// Just output columnns seperated by \t (for easy parsing) (2>/dev/null to ignore erros / warnings)
     #[arg(long)]
     pub simple: bool,
@@ -81,6 +112,14 @@ pub struct ListArgs {
     /// Sort by this value (disables streaming output)
     #[arg(short = 'S', long, value_enum)]
     pub sort: Option<Sorting>,
+
+    /// Only list broken entries (missing files, missing or unparseable .trashinfo files)
+    #[arg(short, long, conflicts_with_all = ["sort", "size"])]
+    pub broken: bool,
+
+    /// Create an index in each trash, which speeds up later lookups and sorting in large trashes
+    #[arg(long, requires = "sort")]
+    pub create_index: bool,
 }
 
 /// List available trashcans on the system
@@ -89,11 +128,18 @@ pub struct ListTrashesArgs {
     /// Just output columnns seperated by \t (for easy parsing) (2>/dev/null to ignore erros / warnings)
     #[arg(short, long)]
     pub simple: bool,
+
+    /// Calculate the size of each trashcan (might take longer)
+    #[arg(long)]
+    pub size: bool,
 }
 
 /// Empty the trash
 #[derive(Debug, Clone, Parser)]
 pub struct EmptyArgs {
+    #[command(flatten)]
+    pub trash: TrashDirArgs,
+
     /// Dry run. Don't delete anything, just print.
     #[arg(short, long)]
     pub dry_run: bool,
@@ -114,19 +160,45 @@ pub struct EmptyArgs {
 /// Restore a file from the trash
 #[derive(Debug, Clone, Parser)]
 pub struct RestoreArgs {
+    #[command(flatten)]
+    pub trash: TrashDirArgs,
+
     /// The ID of a file or it's original
     pub id_or_path: String,
+
+    /// Create an index in each trash, which speeds up later lookups and sorting in large trashes
+    #[arg(long)]
+    pub create_index: bool,
 }
 
 /// Remove broken trashinfo files
 #[derive(Debug, Clone, Parser)]
-pub struct FixArgs {}
+pub struct FixArgs {
+    /// Also remove access for other users from trash directories and trashinfo files
+    #[arg(short, long)]
+    pub permissions: bool,
+}
+
+/// Explain which trashcans are used and why others were rejected
+#[derive(Debug, Clone, Parser)]
+pub struct DoctorArgs {
+    /// Also show filesystems without any trash directory
+    #[arg(short, long)]
+    pub all: bool,
+}
 
 /// Permanently remove a file from the trash
 #[derive(Debug, Clone, Parser)]
 pub struct RemoveArgs {
+    #[command(flatten)]
+    pub trash: TrashDirArgs,
+
     /// The ID of a file or it's original
     pub id_or_path: String,
+
+    /// Create an index in each trash, which speeds up later lookups and sorting in large trashes
+    #[arg(long)]
+    pub create_index: bool,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -1,11 +1,13 @@
-use crate::{streaming_table::StreamingTable, HashID};
+use crate::{cli::TrashDirArgs, streaming_table::StreamingTable};
 use anyhow::Context;
 use std::{
+    collections::HashMap,
+    ffi::OsStr,
     io::{stdin, stdout, Write},
-    path::Path,
+    rc::Rc,
     str::FromStr,
 };
-use xdg_trash::{TrashFile, UnifiedTrash};
+use xdg_trash::{Trash, TrashFile, TrashType, UnifiedTrash, User};
 
 pub fn choose(mut options: Vec<TrashFile>) -> TrashFile {
     if options.len() == 1 {
@@ -72,23 +74,80 @@ pub fn read_generic<T: FromStr>() -> Option<T> {
     read_line().and_then(|x| x.parse().ok())
 }
 
-pub fn list_trashes_matching_status(id_or_path: &str) -> anyhow::Result<Vec<TrashFile>> {
-    let trash = UnifiedTrash::new().context("Failed to init trash")?;
+/// Opens the trash selected with `--trash-dir`, the trashes of the users selected with
+/// `--all-users` or `--user`, or all trashes of the current user on the system
+pub fn open_trash(args: &TrashDirArgs) -> anyhow::Result<UnifiedTrash> {
+    if args.all_users {
+        let users = User::all().context("Failed to read the user database")?;
+        return UnifiedTrash::for_users(&users).context("Failed to init trash");
+    }
+    if let Some(ref name) = args.user {
+        let user =
+            User::from_name(name).with_context(|| format!("Failed to look up user '{name}'"))?;
+        return UnifiedTrash::for_users(&[user]).context("Failed to init trash");
+    }
+    let Some(ref trash_dir) = args.trash_dir else {
+        return UnifiedTrash::new().context("Failed to init trash");
+    };
+    let trash_dir =
+        &std::path::absolute(trash_dir).context("Failed to get the current directory")?;
+
+    // Relative paths are relative to the filesystem the trash is on, which is the parent
+    // of the trash directory, or the parent of `.Trash` for admin trashes
+    let parent = trash_dir.parent().unwrap_or(trash_dir);
+    let (mount_root, trash_type) = if parent.file_name() == Some(OsStr::new(".Trash")) {
+        (parent.parent().unwrap_or(parent), TrashType::Admin)
+    } else {
+        (parent, TrashType::User)
+    };
+
+    let trash = Trash::open(trash_dir, mount_root, true, trash_type)
+        .with_context(|| format!("Failed to open trash at {}", trash_dir.display()))?;
+    Ok(UnifiedTrash::with_trashcans([Rc::new(trash)].into_iter()))
+}
+
+pub fn list_trashes_matching_status(
+    trash_args: &TrashDirArgs,
+    id_or_path: &str,
+    create_index: bool,
+) -> anyhow::Result<Vec<TrashFile>> {
+    let trash = open_trash(trash_args)?;
     print!("Listing files, this might take a moment.");
     stdout().flush().unwrap();
 
-    let matches = trash
-        .list()
+    let indexes: Box<dyn Iterator<Item = _>> = if create_index {
+        Box::new(trash.indexes())
+    } else {
+        Box::new(trash.existing_indexes())
+    };
+    let matches = indexes
         .inspect(|x| {
-            log::debug!("Listing: {x:#?}");
+            log::debug!("Indexed: {x:#?}");
             print!(".");
             stdout().flush().unwrap();
         })
-        .filter_map(Result::ok)
-        .filter(|x| x.id() == id_or_path || x.original_path() == Path::new(&id_or_path))
+        .filter_map(|x| x.map_err(|e| log::error!("{}", e)).ok())
+        .flat_map(|index| index.find(id_or_path).cloned().collect::<Vec<_>>())
+        .map(|x| x.into_trash_file())
         .collect::<Vec<_>>();
     println!();
     println!();
 
     Ok(matches)
 }
+
+/// Resolves uids to user names, looking up every uid only once
+#[derive(Debug, Default)]
+pub struct UserNames(HashMap<u32, String>);
+
+impl UserNames {
+    /// The name of the user with the id `uid`, or the id itself if it has no passwd entry
+    pub fn get(&mut self, uid: u32) -> &str {
+        self.0
+            .entry(uid)
+            .or_insert_with(|| match User::from_uid(uid) {
+                Ok(Some(user)) => user.name,
+                _ => uid.to_string(),
+            })
+    }
+}
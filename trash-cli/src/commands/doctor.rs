@@ -0,0 +1,102 @@
+use crate::{cli::DoctorArgs, streaming_table::StreamingTable};
+use anyhow::Context;
+use xdg_trash::{CandidateStatus, MountReport, RejectReason};
+
+pub fn doctor(args: &DoctorArgs) -> anyhow::Result<()> {
+    let report = xdg_trash::discover().context("Failed to discover trashes")?;
+
+    let display_or_unset = |x: &Option<std::path::PathBuf>| {
+        x.as_ref()
+            .map_or_else(|| "(not set)".to_owned(), |x| x.display().to_string())
+    };
+
+    println!("Home trash:");
+    println!(
+        "  $HOME:          {}",
+        display_or_unset(&report.home.home_dir)
+    );
+    println!(
+        "  $XDG_DATA_HOME: {}",
+        display_or_unset(&report.home.xdg_data_home)
+    );
+    match &report.home.result {
+        Ok(trash) => {
+            let location = trash
+                .info_dir()
+                .parent()
+                .context("Info dir has no parent dir")?;
+            println!("  Location:       {}", location.display());
+            println!("  Device ID:      {}", trash.device());
+            println!(
+                "  Used for:       files on device {} inside of {}",
+                trash.device(),
+                trash.based_on().display()
+            );
+        }
+        Err(e) => println!("  Error:          {e}"),
+    }
+    println!();
+
+    println!("Mounted filesystems ({} examined):", report.mounts.len());
+    let table = StreamingTable::draw_header([
+        ("Status", Some(8)),
+        ("Type", Some(5)),
+        ("Path", Some(30)),
+        ("Reason", None),
+    ]);
+
+    for mount in &report.mounts {
+        let all_missing = mount
+            .candidates
+            .iter()
+            .all(|x| matches!(x.status, CandidateStatus::Rejected(RejectReason::Missing)));
+        if all_missing && !args.all {
+            continue;
+        }
+
+        for candidate in &mount.candidates {
+            let (status, reason) = match &candidate.status {
+                CandidateStatus::Accepted(trash) => {
+                    ("accepted", format!("device {}", trash.device()))
+                }
+                CandidateStatus::Rejected(reason) => ("rejected", reason.to_string()),
+            };
+            table.draw_row([
+                status,
+                &candidate.trash_type.to_string(),
+                &candidate.path.to_string_lossy(),
+                &reason,
+            ]);
+        }
+    }
+
+    if !args.all {
+        println!();
+        println!("Filesystems without any trash directory are hidden, use --all to show them");
+    }
+
+    let trashes = report
+        .home
+        .result
+        .iter()
+        .chain(report.mounts.iter().flat_map(MountReport::accepted));
+    let mut issues = Vec::new();
+    for trash in trashes {
+        match trash.check_permissions(false) {
+            Ok(x) => issues.extend(x),
+            Err(e) => log::warn!("Failed to check permissions: {e}"),
+        }
+    }
+    if !issues.is_empty() {
+        println!();
+        println!("Accessible by other users ({}):", issues.len());
+        let table = StreamingTable::draw_header([("Mode", Some(6)), ("Path", None)]);
+        for issue in &issues {
+            table.draw_row([&format!("{:o}", issue.mode), &issue.path.to_string_lossy()]);
+        }
+        println!();
+        println!("Use `trash fix --permissions` to make them private");
+    }
+
+    Ok(())
+}
@@ -1,12 +1,13 @@
-use crate::cli::EmptyArgs;
-use anyhow::Context;
+use crate::{cli::EmptyArgs, commands::common::open_trash, progress::ProgressIndicator};
 use chrono::{Days, Local};
-use xdg_trash::UnifiedTrash;
+use humansize::DECIMAL;
+use xdg_trash::{EmptyReport, TrashFile};
 
 pub fn empty(args: &EmptyArgs) -> anyhow::Result<()> {
-    let trash = UnifiedTrash::new().context("Failed to init trash")?;
+    let trash = open_trash(&args.trash)?;
+    let filtered = args.before.is_some() || args.after.is_some() || args.keep.is_some();
 
-    for file in trash.list().filter_map(Result::ok).filter(|file| {
+    let predicate = |file: &TrashFile| {
         if let Some(before) = args.before {
             return file.deleted_at() < before;
         }
@@ -23,20 +24,43 @@ pub fn empty(args: &EmptyArgs) -> anyhow::Result<()> {
         }
 
         true
-    }) {
-        let p = file.original_path();
-        if args.dry_run {
-            println!("Would remove: {}", p.display());
-            continue;
-        }
+    };
 
-        let orig_path = file.original_path();
-        if let Err((_, e)) = file.remove() {
-            log::error!("Failed to remove file: {e}");
-        } else {
-            println!("Removed {}", orig_path.display());
+    if args.dry_run {
+        for file in trash.list().filter_map(Result::ok).filter(predicate) {
+            println!("Would remove: {}", file.original_path().display());
         }
+        return Ok(());
     }
 
+    let mut progress = ProgressIndicator::new("Removing");
+    let report = if filtered {
+        trash.remove_where_with_progress(predicate, &mut progress)
+    } else {
+        // Also removes broken entries
+        trash.empty_with_progress(&mut progress)
+    };
+    progress.finish();
+
+    print_report(&report)
+}
+
+fn print_report(report: &EmptyReport) -> anyhow::Result<()> {
+    for path in &report.removed {
+        println!("Removed {}", path.display());
+    }
+    for (_, e) in &report.failed {
+        log::error!("Failed to remove file: {e}");
+    }
+
+    println!(
+        "Removed {} entries, freed {}",
+        report.removed.len(),
+        humansize::format_size(report.bytes_freed, DECIMAL)
+    );
+
+    if report.cancelled {
+        anyhow::bail!("Interrupted, the remaining files were not removed");
+    }
     Ok(())
 }
@@ -1,11 +1,30 @@
-use crate::cli::FixArgs;
+use crate::{cli::FixArgs, progress::ProgressIndicator};
 use anyhow::Context;
 use xdg_trash::UnifiedTrash;
 
-pub fn fix(_args: &FixArgs) -> anyhow::Result<()> {
+pub fn fix(args: &FixArgs) -> anyhow::Result<()> {
     let trash = UnifiedTrash::new().context("Failed to init trash")?;
-    let amount = trash.fix().context("Failed to fix trashinfo files")?;
+    let mut progress = ProgressIndicator::new("Checking");
+    let amount = trash
+        .fix_with_progress(&mut progress)
+        .context("Failed to fix trashinfo files")?;
+    progress.finish();
     println!("Removed {} trashinfo files", amount);
 
+    if args.permissions {
+        let issues = trash
+            .check_permissions(true)
+            .context("Failed to fix permissions")?;
+        for issue in &issues {
+            println!(
+                "Changed mode of {} from {:o} to {:o}",
+                issue.path.display(),
+                issue.mode,
+                issue.expected
+            );
+        }
+        println!("Fixed permissions of {} files", issues.len());
+    }
+
     Ok(())
 }
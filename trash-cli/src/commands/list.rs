@@ -1,73 +1,113 @@
 use crate::{
     cli::{ListArgs, Sorting},
+    commands::common::{open_trash, UserNames},
     streaming_table::StreamingTable,
-    HashID, ID_LEN,
 };
 use anyhow::Context;
 use humansize::DECIMAL;
-use xdg_trash::{TrashFile, UnifiedTrash};
+use xdg_trash::{RawEntry, TrashFile, UnifiedTrash, ID_LEN};
 
 #[derive(Debug)]
-enum TableDisplay<A, B> {
+enum TableDisplay<A, B, C, D> {
     NoTrash(A),
     WithTrash(B),
+    NoTrashWithOwner(C),
+    WithTrashAndOwner(D),
 }
 
 pub fn list(mut args: ListArgs) -> anyhow::Result<()> {
-    let trash = UnifiedTrash::new().unwrap();
+    let trash = open_trash(&args.trash)?;
+
+    if args.broken {
+        return list_broken(&trash, args.simple);
+    }
 
     // I'd like for this to be done in clap, but I couldn't figure out a way to do it entirely in clap :(
     if let Some(Sorting::Size) = args.sort {
         args.size = true;
     }
 
-    let list: Box<dyn Iterator<Item = TrashFile>> = {
-        let list = trash
-            .list()
+    let list: Box<dyn Iterator<Item = TrashFile>> = if let Some(sorting) = args.sort {
+        // Sorting needs all entries up front anyway, so the index can be used
+        let indexes: Box<dyn Iterator<Item = _>> = if args.create_index {
+            Box::new(trash.indexes())
+        } else {
+            Box::new(trash.existing_indexes())
+        };
+        let mut vec = indexes
             .inspect(|x| {
                 if let Err(e) = x {
                     log::error!("{}", e);
                 }
             })
-            .filter_map(Result::ok);
-
-        if let Some(sorting) = args.sort {
-            let mut vec = list.collect::<Vec<_>>();
-            vec.sort_by(|a, b| match sorting {
-                Sorting::Trash => a.trash().mount_root().cmp(b.trash().mount_root()),
-                Sorting::Path => a.original_path().cmp(&b.original_path()),
-                Sorting::Date => a.deleted_at().cmp(&b.deleted_at()),
-                // TODO Replacing the size with zero upon failure might not be the best option here
-                Sorting::Size => a.size().unwrap_or(0).cmp(&b.size().unwrap_or(0)),
-            });
-
-            if args.reverse {
-                vec.reverse();
-            }
+            .filter_map(Result::ok)
+            .flat_map(|x| x.into_entries())
+            .collect::<Vec<_>>();
 
-            Box::new(vec.into_iter())
-        } else {
-            Box::new(list)
+        vec.sort_by(|a, b| match sorting {
+            Sorting::Trash => a.trash().mount_root().cmp(b.trash().mount_root()),
+            Sorting::Path => a.original_path().cmp(&b.original_path()),
+            Sorting::Date => a.deleted_at().cmp(&b.deleted_at()),
+            // TODO Replacing the size with zero upon failure might not be the best option here
+            Sorting::Size => a.size().unwrap_or(0).cmp(&b.size().unwrap_or(0)),
+        });
+
+        if args.reverse {
+            vec.reverse();
         }
+
+        Box::new(vec.into_iter().map(|x| x.into_trash_file()))
+    } else {
+        Box::new(
+            trash
+                .list()
+                .inspect(|x| {
+                    if let Err(e) = x {
+                        log::error!("{}", e);
+                    }
+                })
+                .filter_map(Result::ok),
+        )
     };
 
-    let table = match (args.trash_location, args.simple) {
-        (true, false) => Some(TableDisplay::WithTrash(StreamingTable::draw_header([
+    let show_owner = args.trash.other_users();
+    let table = match (args.trash_location, show_owner, args.simple) {
+        (true, false, false) => Some(TableDisplay::WithTrash(StreamingTable::draw_header([
             ("ID", Some(ID_LEN)),
             ("Deleted at", Some(19)),
             ("Size", Some(8)),
             ("Trash location", Some(40)),
             ("Original Location", None),
         ]))),
-        (false, false) => Some(TableDisplay::NoTrash(StreamingTable::draw_header([
+        (false, false, false) => Some(TableDisplay::NoTrash(StreamingTable::draw_header([
             ("ID", Some(ID_LEN)),
             ("Deleted at", Some(19)),
             ("Size", Some(8)),
             ("Original Location", None),
         ]))),
-        (_, true) => None,
+        (true, true, false) => Some(TableDisplay::WithTrashAndOwner(
+            StreamingTable::draw_header([
+                ("ID", Some(ID_LEN)),
+                ("Deleted at", Some(19)),
+                ("Size", Some(8)),
+                ("Owner", Some(12)),
+                ("Trash location", Some(40)),
+                ("Original Location", None),
+            ]),
+        )),
+        (false, true, false) => Some(TableDisplay::NoTrashWithOwner(StreamingTable::draw_header(
+            [
+                ("ID", Some(ID_LEN)),
+                ("Deleted at", Some(19)),
+                ("Size", Some(8)),
+                ("Owner", Some(12)),
+                ("Original Location", None),
+            ],
+        ))),
+        (_, _, true) => None,
     };
 
+    let mut user_names = UserNames::default();
     let mut total_size = if args.size { Some(0) } else { None };
 
     for file in list {
@@ -89,6 +129,8 @@ pub fn list(mut args: ListArgs) -> anyhow::Result<()> {
             .parent()
             .context("Info dir has no parent")?;
 
+        let owner = user_names.get(file.trash().owner());
+
         match table {
             Some(TableDisplay::NoTrash(ref table)) => {
                 table.draw_row([
@@ -107,13 +149,33 @@ pub fn list(mut args: ListArgs) -> anyhow::Result<()> {
                     &orig_path.to_string_lossy(),
                 ]);
             }
+            Some(TableDisplay::NoTrashWithOwner(ref table)) => {
+                table.draw_row([id, del_at, &size_human, owner, &orig_path.to_string_lossy()]);
+            }
+            Some(TableDisplay::WithTrashAndOwner(ref table)) => {
+                table.draw_row([
+                    id,
+                    del_at,
+                    &size_human,
+                    owner,
+                    &trash.to_string_lossy(),
+                    &orig_path.to_string_lossy(),
+                ]);
+            }
             None => {
+                // The owner is only added for other users, to not break existing parsers
+                let owner = if show_owner {
+                    format!("{owner}\t")
+                } else {
+                    String::new()
+                };
                 println!(
-                    "{}\t{}\t{}\t{}\t{}",
+                    "{}\t{}\t{}\t{}\t{}{}",
                     id,
                     del_at,
                     size.map_or_else(|| "N/A".to_owned(), |x| x.to_string()),
                     trash.display(),
+                    owner,
                     orig_path.display()
                 );
             }
@@ -132,3 +194,47 @@ pub fn list(mut args: ListArgs) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+fn list_broken(trash: &UnifiedTrash, simple: bool) -> anyhow::Result<()> {
+    let table = (!simple).then(|| {
+        StreamingTable::draw_header([("Problem", Some(20)), ("Path", Some(60)), ("Details", None)])
+    });
+
+    for entry in trash.list_raw() {
+        let entry = match entry {
+            Ok(x) => x,
+            Err(e) => {
+                log::error!("{}", e);
+                continue;
+            }
+        };
+
+        let (problem, path, details) = match &entry {
+            RawEntry::Valid(_) => continue,
+            RawEntry::MissingPayload {
+                entry,
+                original_path,
+                ..
+            } => (
+                "Missing file",
+                entry.info_filepath(),
+                format!("originally at {}", original_path.display()),
+            ),
+            RawEntry::MissingInfo { entry } => {
+                ("Missing trashinfo", entry.files_filepath(), String::new())
+            }
+            RawEntry::Unparseable { entry, error, .. } => (
+                "Invalid trashinfo",
+                entry.info_filepath(),
+                error.to_string(),
+            ),
+        };
+
+        match table {
+            Some(ref table) => table.draw_row([problem, &path.to_string_lossy(), &details]),
+            None => println!("{}\t{}\t{}", problem, path.display(), details),
+        }
+    }
+
+    Ok(())
+}
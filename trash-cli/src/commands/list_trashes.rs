@@ -1,17 +1,29 @@
-use crate::{cli::ListTrashesArgs, streaming_table::StreamingTable};
+use crate::{cli::ListTrashesArgs, progress::ProgressIndicator, streaming_table::StreamingTable};
 use anyhow::Context;
+use humansize::DECIMAL;
+
+#[derive(Debug)]
+enum TableDisplay<A, B> {
+    NoSize(A),
+    WithSize(B),
+}
 
 pub fn list_trashes(args: &ListTrashesArgs) -> anyhow::Result<()> {
     let trashes = xdg_trash::list_trashes().context("Failed to list trashes")?;
 
-    let table = if !args.simple {
-        Some(StreamingTable::draw_header([
+    let table = match (args.size, args.simple) {
+        (false, false) => Some(TableDisplay::NoSize(StreamingTable::draw_header([
+            ("Device ID", Some(9)),
+            ("Type", Some(5)),
+            ("Path", Some(15)),
+        ]))),
+        (true, false) => Some(TableDisplay::WithSize(StreamingTable::draw_header([
             ("Device ID", Some(9)),
             ("Type", Some(5)),
+            ("Size", Some(8)),
             ("Path", Some(15)),
-        ]))
-    } else {
-        None
+        ]))),
+        (_, true) => None,
     };
 
     for trash in trashes {
@@ -23,10 +35,43 @@ pub fn list_trashes(args: &ListTrashesArgs) -> anyhow::Result<()> {
             .context("Info dir has no parent dir")?
             .to_string_lossy();
 
-        if let Some(ref table) = table {
-            table.draw_row([&dev, &trash_type, &location]);
+        let size = if args.size {
+            let mut progress = ProgressIndicator::new("Calculating size");
+            let size = trash.size_with_progress(&mut progress);
+            progress.finish();
+            match size {
+                Ok(x) => Some(x),
+                Err(e) if e.kind() == xdg_trash::ErrorKind::Cancelled => return Err(e.into()),
+                Err(e) => {
+                    log::error!("{}", e);
+                    None
+                }
+            }
         } else {
-            println!("{}\t{}\t{}\t", dev, trash_type, location);
+            None
+        };
+        let size_human =
+            size.map_or_else(|| "N/A".to_owned(), |x| humansize::format_size(x, DECIMAL));
+
+        match table {
+            Some(TableDisplay::NoSize(ref table)) => {
+                table.draw_row([&dev, &trash_type, &location]);
+            }
+            Some(TableDisplay::WithSize(ref table)) => {
+                table.draw_row([&dev, &trash_type, &size_human, &location]);
+            }
+            None if args.size => {
+                println!(
+                    "{}\t{}\t{}\t{}\t",
+                    dev,
+                    trash_type,
+                    size.map_or_else(|| "N/A".to_owned(), |x| x.to_string()),
+                    location
+                );
+            }
+            None => {
+                println!("{}\t{}\t{}\t", dev, trash_type, location);
+            }
         }
     }
 
@@ -1,4 +1,5 @@
 mod common;
+mod doctor;
 mod empty;
 mod fix;
 mod list;
@@ -7,6 +8,7 @@ mod put;
 mod remove;
 mod restore;
 
+pub use doctor::*;
 pub use empty::*;
 pub use fix::*;
 pub use list::*;
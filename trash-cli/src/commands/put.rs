@@ -1,28 +1,49 @@
-use crate::cli::PutArgs;
+use crate::{cli::PutArgs, progress::ProgressIndicator};
 use anyhow::Context;
-use std::rc::Rc;
-use xdg_trash::{Trash, UnifiedTrash};
+use std::{ops::ControlFlow, rc::Rc};
+use xdg_trash::{ErrorKind, Progress, ProgressHandler, Trash, UnifiedTrash, User};
 
 pub fn put(args: PutArgs) -> anyhow::Result<()> {
-    let home_trash =
-        Trash::find_or_create_home_trash().context("Failed to init the home trashcan")?;
+    let invoking = if args.as_root {
+        None
+    } else {
+        User::invoking().context("Failed to look up the invoking user")?
+    };
 
-    let mut trash = UnifiedTrash::with_trashcans([Rc::new(home_trash)].into_iter());
+    // Under sudo, files go into the trash of the invoking user, so they can see and restore them
+    let mut trash = if let Some(user) = invoking {
+        log::debug!("Using the trash of the invoking user {}", user.name);
+        UnifiedTrash::as_user(&user).context("Failed to find the trashcans of the invoking user")?
+    } else {
+        let home_trash = Trash::find_home_trash().context("Failed to find the home trashcan")?;
+        UnifiedTrash::with_trashcans([Rc::new(home_trash)].into_iter())
+    };
 
-    for file in args.files {
-        match trash.put(&file) {
+    // Without --force, stop at the first file that can't be trashed
+    let mut progress = ProgressIndicator::new("Trashing");
+    let results = trash.put_all(&args.files, &mut |p: &Progress| {
+        if p.items_failed > 0 && !args.force {
+            return ControlFlow::Break(());
+        }
+        progress.update(p)
+    });
+    progress.finish();
+
+    let mut cancelled = 0;
+    for (file, result) in args.files.iter().zip(results) {
+        match result {
             Ok(_) => {
                 println!("Trashed {}", file.display());
             }
-            Err(e) => {
-                if args.force {
-                    log::error!("{}", e);
-                } else {
-                    return Err(anyhow::anyhow!(e));
-                }
-            }
+            Err(e) if e.kind() == ErrorKind::Cancelled => cancelled += 1,
+            Err(e) if args.force => log::error!("{}", e),
+            Err(e) => return Err(anyhow::anyhow!(e)),
         }
     }
 
+    if cancelled > 0 {
+        anyhow::bail!("Interrupted, {} files were not trashed", cancelled);
+    }
+
     Ok(())
 }
@@ -5,7 +5,7 @@ use crate::{
 use anyhow::Context;
 
 pub fn remove(args: &RemoveArgs) -> anyhow::Result<()> {
-    let matches = list_trashes_matching_status(&args.id_or_path)?;
+    let matches = list_trashes_matching_status(&args.trash, &args.id_or_path, args.create_index)?;
 
     if matches.is_empty() {
         anyhow::bail!("No matching items found!");
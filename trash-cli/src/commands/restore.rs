@@ -3,9 +3,10 @@ use crate::{
     commands::common::{ask_yes_no, choose, list_trashes_matching_status},
 };
 use anyhow::Context;
+use xdg_trash::ErrorKind;
 
 pub fn restore(args: &RestoreArgs) -> anyhow::Result<()> {
-    let matches = list_trashes_matching_status(&args.id_or_path)?;
+    let matches = list_trashes_matching_status(&args.trash, &args.id_or_path, args.create_index)?;
 
     if matches.is_empty() {
         anyhow::bail!("No matching items found!");
@@ -15,9 +16,9 @@ pub fn restore(args: &RestoreArgs) -> anyhow::Result<()> {
     let orig_path = choice.original_path();
 
     if let Err((choice, e)) = choice.restore(false) {
-        match e {
-            xdg_trash::Error::AlreadyExists(ref p) => {
-                println!("A file already exists at {}\n", p.display());
+        match e.kind() {
+            ErrorKind::Conflict => {
+                println!("A file already exists at {}\n", orig_path.display());
                 if ask_yes_no("Do you wan to overwrite it?", false) {
                     choice
                         .restore(true)
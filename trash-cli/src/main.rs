@@ -1,10 +1,9 @@
 use clap::Parser;
-use sha2::{Digest, Sha256};
-use std::{env, fmt::Write, os::unix::ffi::OsStrExt, path::Path, process::ExitCode};
-use xdg_trash::TrashFile;
+use std::{env, path::Path, process::ExitCode};
 
 mod cli;
 mod commands;
+mod progress;
 mod streaming_table;
 
 fn main() -> ExitCode {
@@ -51,6 +50,7 @@ fn main() -> ExitCode {
                 cli::SubCmd::Remove(args) => commands::remove(&args),
                 cli::SubCmd::ListTrashes(args) => commands::list_trashes(&args),
                 cli::SubCmd::Fix(args) => commands::fix(&args),
+                cli::SubCmd::Doctor(args) => commands::doctor(&args),
             }
         }
     };
@@ -62,29 +62,3 @@ fn main() -> ExitCode {
         ExitCode::SUCCESS
     }
 }
-
-pub const ID_LEN: usize = 10;
-
-pub trait HashID {
-    fn file_bytes(&self) -> Vec<u8>;
-
-    fn id(&self) -> String {
-        pub fn encode_hex(bytes: &[u8]) -> String {
-            let mut s = String::with_capacity(bytes.len() * 2);
-            for &b in bytes {
-                write!(&mut s, "{:02x}", b).unwrap();
-            }
-            s
-        }
-
-        let hash = Sha256::digest(self.file_bytes());
-        let hash = hash.as_slice();
-        encode_hex(hash).chars().take(ID_LEN).collect()
-    }
-}
-
-impl HashID for TrashFile {
-    fn file_bytes(&self) -> Vec<u8> {
-        self.original_path().as_os_str().as_bytes().to_vec()
-    }
-}
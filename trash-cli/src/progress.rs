@@ -0,0 +1,101 @@
+use humansize::DECIMAL;
+use std::{
+    io::{self, IsTerminal, Write},
+    ops::ControlFlow,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once,
+    },
+    time::{Duration, Instant},
+};
+use xdg_trash::{Progress, ProgressHandler};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static INSTALL_HANDLER: Once = Once::new();
+
+/// Draws the progress of an operation to stderr and cancels it on Ctrl-C.
+///
+/// The first Ctrl-C lets the current item finish, the second one exits immediately.
+pub struct ProgressIndicator {
+    label: &'static str,
+    visible: bool,
+    last_draw: Option<Instant>,
+}
+
+impl ProgressIndicator {
+    pub fn new(label: &'static str) -> Self {
+        INSTALL_HANDLER.call_once(|| {
+            let result = ctrlc::set_handler(|| {
+                if INTERRUPTED.swap(true, Ordering::SeqCst) {
+                    process::exit(130);
+                }
+            });
+            if let Err(e) = result {
+                log::warn!("Failed to install Ctrl-C handler: {}", e);
+            }
+        });
+
+        Self {
+            label,
+            visible: io::stderr().is_terminal(),
+            last_draw: None,
+        }
+    }
+
+    pub fn interrupted(&self) -> bool {
+        INTERRUPTED.load(Ordering::SeqCst)
+    }
+
+    /// Clears the progress line
+    pub fn finish(&mut self) {
+        if self.last_draw.take().is_some() {
+            eprint!("\r\x1b[2K");
+        }
+    }
+
+    fn draw(&mut self, progress: &Progress) {
+        let finished = progress.items_done == progress.items_total;
+        if !self.visible
+            || (!finished
+                && self
+                    .last_draw
+                    .is_some_and(|x| x.elapsed() < Duration::from_millis(100)))
+        {
+            return;
+        }
+
+        let mut line = format!(
+            "\r\x1b[2K{}: {}/{}",
+            self.label, progress.items_done, progress.items_total
+        );
+        if progress.bytes_done > 0 {
+            line.push_str(&format!(
+                " ({})",
+                humansize::format_size(progress.bytes_done, DECIMAL)
+            ));
+        }
+        let mut stderr = io::stderr().lock();
+        _ = stderr.write_all(line.as_bytes());
+        _ = stderr.flush();
+        self.last_draw = Some(Instant::now());
+    }
+}
+
+impl ProgressHandler for ProgressIndicator {
+    fn update(&mut self, progress: &Progress) -> ControlFlow<()> {
+        if self.interrupted() {
+            self.finish();
+            return ControlFlow::Break(());
+        }
+
+        self.draw(progress);
+        ControlFlow::Continue(())
+    }
+}
+
+impl Drop for ProgressIndicator {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
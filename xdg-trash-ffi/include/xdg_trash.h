@@ -0,0 +1,202 @@
+/* C bindings for xdg-trash. Generated with cbindgen, regenerate with `XDG_TRASH_BLESS=1 cargo test -p xdg-trash-ffi`. */
+
+#ifndef XDG_TRASH_H
+#define XDG_TRASH_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of every fallible function, `XDG_TRASH_STATUS_OK` on success
+typedef enum XdgTrashStatus {
+  // The call succeeded
+  XDG_TRASH_STATUS_OK = 0,
+  // A required pointer was null, or an entry was already restored or removed
+  XDG_TRASH_STATUS_INVALID_ARGUMENT,
+  // Permission denied
+  XDG_TRASH_STATUS_PERMISSION_DENIED,
+  // A file or directory was not found
+  XDG_TRASH_STATUS_NOT_FOUND,
+  // The filesystem is read-only
+  XDG_TRASH_STATUS_READ_ONLY_FILESYSTEM,
+  // The file is on a different device than the trash
+  XDG_TRASH_STATUS_CROSS_DEVICE,
+  // A file already exists at the destination
+  XDG_TRASH_STATUS_CONFLICT,
+  // A `.trashinfo` file is invalid
+  XDG_TRASH_STATUS_INVALID_TRASHINFO,
+  // Some entries could not be removed, see `xdg_trash_empty`
+  XDG_TRASH_STATUS_PARTIAL_FAILURE,
+  // A panic was caught at the library boundary, this is a bug
+  XDG_TRASH_STATUS_PANIC,
+  // Any other error
+  XDG_TRASH_STATUS_OTHER,
+} XdgTrashStatus;
+
+// A handle to all trashcans on the system, see [`UnifiedTrash`]
+typedef struct XdgTrash XdgTrash;
+
+// A trashed file
+typedef struct XdgTrashEntry XdgTrashEntry;
+
+// Iterates over the entries of all trashcans, see [`xdg_trash_list`]
+typedef struct XdgTrashIter XdgTrashIter;
+
+// Options for [`xdg_trash_entry_restore`]
+typedef struct XdgTrashRestoreOptions {
+  // Replace a file that exists at the original location
+  bool overwrite;
+} XdgTrashRestoreOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a handle to all trashcans on the system.
+//
+// # Safety
+// `out` must be a valid pointer, the handle written to it must be freed with [`xdg_trash_free`].
+enum XdgTrashStatus xdg_trash_new(struct XdgTrash **out);
+
+// Creates a handle that only knows the home trash of the current user.
+//
+// Listing and emptying only affect the home trash. Putting a file from another
+// filesystem still finds or creates a trash on it, like [`xdg_trash_new`].
+//
+// # Safety
+// `out` must be a valid pointer, the handle written to it must be freed with [`xdg_trash_free`].
+enum XdgTrashStatus xdg_trash_new_home(struct XdgTrash **out);
+
+// Frees a handle created by [`xdg_trash_new`] or [`xdg_trash_new_home`], null is ignored.
+//
+// # Safety
+// `trash` must be null or a handle that was not freed yet.
+void xdg_trash_free(struct XdgTrash *trash);
+
+// Puts the file at `path` into the trash, see [`UnifiedTrash::put`].
+//
+// If `out` is not null, the created entry is written to it.
+//
+// # Safety
+// `trash` must be a valid handle and `path` a nul-terminated string.
+// `out` must be null or a valid pointer, the entry written to it must be freed with [`xdg_trash_entry_free`].
+enum XdgTrashStatus xdg_trash_put(struct XdgTrash *trash,
+                                  const char *path,
+                                  struct XdgTrashEntry **out);
+
+// Lists all entries of all trashcans. The entries are read up front,
+// later changes to the trash are not reflected by the iterator.
+//
+// # Safety
+// `trash` must be a valid handle and `out` a valid pointer,
+// the iterator written to it must be freed with [`xdg_trash_iter_free`].
+enum XdgTrashStatus xdg_trash_list(const struct XdgTrash *trash, struct XdgTrashIter **out);
+
+// Advances the iterator, writing the next entry to `out`.
+//
+// At the end, `XDG_TRASH_STATUS_OK` is returned and null is written to `out`.
+// Broken entries return an error and write null, iteration can continue after them.
+//
+// # Safety
+// `iter` must be a valid iterator and `out` a valid pointer,
+// the entry written to it must be freed with [`xdg_trash_entry_free`].
+enum XdgTrashStatus xdg_trash_iter_next(struct XdgTrashIter *iter, struct XdgTrashEntry **out);
+
+// Frees an iterator created by [`xdg_trash_list`], null is ignored.
+// Entries returned by it stay valid.
+//
+// # Safety
+// `iter` must be null or an iterator that was not freed yet.
+void xdg_trash_iter_free(struct XdgTrashIter *iter);
+
+// The short ID of the entry, see [`TrashFile::id`]. Returns null if `entry` is null.
+//
+// # Safety
+// `entry` must be null or a valid entry, the string is valid as long as the entry.
+const char *xdg_trash_entry_id(const struct XdgTrashEntry *entry);
+
+// The location the entry is restored to. Returns null if `entry` is null.
+//
+// # Safety
+// `entry` must be null or a valid entry, the string is valid as long as the entry.
+const char *xdg_trash_entry_original_path(const struct XdgTrashEntry *entry);
+
+// The path of the entries `.trashinfo` file. Returns null if `entry` is null.
+//
+// # Safety
+// `entry` must be null or a valid entry, the string is valid as long as the entry.
+const char *xdg_trash_entry_info_path(const struct XdgTrashEntry *entry);
+
+// The path of the trashed file inside the trash. Returns null if `entry` is null.
+//
+// # Safety
+// `entry` must be null or a valid entry, the string is valid as long as the entry.
+const char *xdg_trash_entry_files_path(const struct XdgTrashEntry *entry);
+
+// The time the entry was trashed as a unix timestamp, assuming the
+// `.trashinfo` file uses local time. Returns 0 if `entry` is null.
+//
+// # Safety
+// `entry` must be null or a valid entry.
+int64_t xdg_trash_entry_deleted_at(const struct XdgTrashEntry *entry);
+
+// Restores the entry to it's original location, see [`TrashFile::restore`].
+//
+// `options` may be null to use the defaults. If `out_path` is not null, the restored
+// path is written to it and must be freed with [`xdg_trash_string_free`].
+// Once restored, the entry can only be freed, other operations fail.
+//
+// # Safety
+// `entry` must be a valid entry, `options` and `out_path` must be null or valid pointers.
+enum XdgTrashStatus xdg_trash_entry_restore(struct XdgTrashEntry *entry,
+                                            const struct XdgTrashRestoreOptions *options,
+                                            char **out_path);
+
+// Permanently removes the entry from the trash, see [`TrashFile::remove`].
+// Once removed, the entry can only be freed, other operations fail.
+//
+// # Safety
+// `entry` must be a valid entry.
+enum XdgTrashStatus xdg_trash_entry_remove(struct XdgTrashEntry *entry);
+
+// Frees an entry, null is ignored.
+//
+// # Safety
+// `entry` must be null or an entry that was not freed yet.
+void xdg_trash_entry_free(struct XdgTrashEntry *entry);
+
+// Permanently removes all entries from all trashcans, see [`UnifiedTrash::empty`].
+//
+// The amount of removed and failed entries and the freed bytes are written to the
+// out-parameters that are not null. If some entries could not be removed,
+// `XDG_TRASH_STATUS_PARTIAL_FAILURE` is returned and the last error message
+// describes the first failure.
+//
+// # Safety
+// `trash` must be a valid handle, the out-parameters must be null or valid pointers.
+enum XdgTrashStatus xdg_trash_empty(struct XdgTrash *trash,
+                                    uint64_t *out_removed,
+                                    uint64_t *out_failed,
+                                    uint64_t *out_bytes_freed);
+
+// Frees a string returned through an out-parameter, null is ignored.
+//
+// # Safety
+// `s` must be null or a string returned by this library that was not freed yet.
+void xdg_trash_string_free(char *s);
+
+// Returns the message of the last error on the calling thread, or null if there was none.
+//
+// The string is owned by the library and stays valid until the next failing call on this thread.
+const char *xdg_trash_last_error_message(void);
+
+// Returns a static, human readable description of `status`
+const char *xdg_trash_status_str(enum XdgTrashStatus status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* XDG_TRASH_H */
@@ -0,0 +1,90 @@
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CString},
+    ptr,
+};
+use xdg_trash::ErrorKind;
+
+/// The result of every fallible function, `XDG_TRASH_STATUS_OK` on success
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XdgTrashStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer was null, or an entry was already restored or removed
+    InvalidArgument,
+    /// Permission denied
+    PermissionDenied,
+    /// A file or directory was not found
+    NotFound,
+    /// The filesystem is read-only
+    ReadOnlyFilesystem,
+    /// The file is on a different device than the trash
+    CrossDevice,
+    /// A file already exists at the destination
+    Conflict,
+    /// A `.trashinfo` file is invalid
+    InvalidTrashinfo,
+    /// Some entries could not be removed, see `xdg_trash_empty`
+    PartialFailure,
+    /// A panic was caught at the library boundary, this is a bug
+    Panic,
+    /// Any other error
+    Other,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records `e` as the last error of this thread and returns it's status code
+pub(crate) fn set_error(e: &xdg_trash::Error) -> XdgTrashStatus {
+    set_message(&e.to_string());
+    match e.kind() {
+        ErrorKind::PermissionDenied => XdgTrashStatus::PermissionDenied,
+        ErrorKind::NotFound => XdgTrashStatus::NotFound,
+        ErrorKind::ReadOnlyFilesystem => XdgTrashStatus::ReadOnlyFilesystem,
+        ErrorKind::CrossDevice => XdgTrashStatus::CrossDevice,
+        ErrorKind::Conflict => XdgTrashStatus::Conflict,
+        ErrorKind::InvalidTrashinfo => XdgTrashStatus::InvalidTrashinfo,
+        _ => XdgTrashStatus::Other,
+    }
+}
+
+pub(crate) fn set_message(message: &str) {
+    // Interior nul bytes can't be represented, they are never part of our messages anyways
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|x| *x.borrow_mut() = Some(message));
+}
+
+pub(crate) fn invalid_argument(message: &str) -> XdgTrashStatus {
+    set_message(message);
+    XdgTrashStatus::InvalidArgument
+}
+
+/// Returns the message of the last error on the calling thread, or null if there was none.
+///
+/// The string is owned by the library and stays valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn xdg_trash_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|x| x.borrow().as_ref().map_or(ptr::null(), |x| x.as_ptr()))
+}
+
+/// Returns a static, human readable description of `status`
+#[no_mangle]
+pub extern "C" fn xdg_trash_status_str(status: XdgTrashStatus) -> *const c_char {
+    let s: &'static [u8] = match status {
+        XdgTrashStatus::Ok => b"ok\0",
+        XdgTrashStatus::InvalidArgument => b"invalid argument\0",
+        XdgTrashStatus::PermissionDenied => b"permission denied\0",
+        XdgTrashStatus::NotFound => b"not found\0",
+        XdgTrashStatus::ReadOnlyFilesystem => b"read-only filesystem\0",
+        XdgTrashStatus::CrossDevice => b"cross-device operation\0",
+        XdgTrashStatus::Conflict => b"a file already exists at the destination\0",
+        XdgTrashStatus::InvalidTrashinfo => b"invalid trashinfo file\0",
+        XdgTrashStatus::PartialFailure => b"some entries could not be removed\0",
+        XdgTrashStatus::Panic => b"internal error\0",
+        XdgTrashStatus::Other => b"other error\0",
+    };
+    s.as_ptr().cast()
+}
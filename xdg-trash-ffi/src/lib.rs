@@ -0,0 +1,410 @@
+//! C bindings for [`xdg_trash`].
+//!
+//! The header is checked in at `include/xdg_trash.h` and generated with cbindgen,
+//! the `header_is_up_to_date` test fails if it's outdated.
+//!
+//! ## Conventions
+//! - Fallible functions return a [`XdgTrashStatus`], the message of the last error on the
+//!   calling thread is available through [`xdg_trash_last_error_message`].
+//! - Handles are opaque and must be freed with their `_free` function. They use reference counting
+//!   internally and must not be shared between threads.
+//! - Paths are passed as nul-terminated byte strings, they don't have to be valid UTF-8.
+//! - Strings returned by accessors are owned by the handle, strings returned through
+//!   out-parameters are owned by the caller and freed with [`xdg_trash_string_free`].
+
+mod error;
+
+pub use error::{xdg_trash_last_error_message, xdg_trash_status_str, XdgTrashStatus};
+
+use chrono::{Local, TimeZone};
+use error::{invalid_argument, set_error, set_message};
+use std::{
+    ffi::{c_char, CStr, CString, OsStr},
+    os::unix::ffi::OsStrExt,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    ptr,
+    rc::Rc,
+    vec,
+};
+use xdg_trash::{Trash, TrashFile, UnifiedTrash};
+
+/// A handle to all trashcans on the system, see [`UnifiedTrash`]
+pub struct XdgTrash {
+    inner: UnifiedTrash,
+}
+
+/// Iterates over the entries of all trashcans, see [`xdg_trash_list`]
+pub struct XdgTrashIter {
+    entries: vec::IntoIter<xdg_trash::Result<TrashFile>>,
+}
+
+/// A trashed file
+pub struct XdgTrashEntry {
+    /// `None` once the file was restored or removed
+    file: Option<TrashFile>,
+    id: CString,
+    original_path: CString,
+    info_path: CString,
+    files_path: CString,
+    deleted_at: i64,
+}
+
+/// Options for [`xdg_trash_entry_restore`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XdgTrashRestoreOptions {
+    /// Replace a file that exists at the original location
+    pub overwrite: bool,
+}
+
+impl XdgTrashEntry {
+    fn new(file: TrashFile) -> Self {
+        let deleted_at = file.deleted_at();
+        Self {
+            id: c_string(file.id().as_bytes()),
+            original_path: c_string(file.original_path().as_os_str().as_bytes()),
+            info_path: c_string(file.info_filepath().as_os_str().as_bytes()),
+            files_path: c_string(file.files_filepath().as_os_str().as_bytes()),
+            deleted_at: Local
+                .from_local_datetime(&deleted_at)
+                .earliest()
+                .map_or(deleted_at.and_utc().timestamp(), |x| x.timestamp()),
+            file: Some(file),
+        }
+    }
+}
+
+fn c_string(bytes: &[u8]) -> CString {
+    // Paths can't contain nul bytes
+    CString::new(bytes).unwrap_or_default()
+}
+
+/// Runs `f`, turning a panic into [`XdgTrashStatus::Panic`], as unwinding into C is undefined
+fn guard(f: impl FnOnce() -> XdgTrashStatus) -> XdgTrashStatus {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        set_message("xdg-trash panicked");
+        XdgTrashStatus::Panic
+    })
+}
+
+unsafe fn path_arg<'a>(path: *const c_char) -> Option<&'a Path> {
+    if path.is_null() {
+        return None;
+    }
+    Some(Path::new(OsStr::from_bytes(
+        CStr::from_ptr(path).to_bytes(),
+    )))
+}
+
+/// Writes `value` to `out`, if `out` is not null
+unsafe fn write_opt<T>(out: *mut T, value: T) {
+    if !out.is_null() {
+        out.write(value);
+    }
+}
+
+/// Creates a handle to all trashcans on the system.
+///
+/// # Safety
+/// `out` must be a valid pointer, the handle written to it must be freed with [`xdg_trash_free`].
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_new(out: *mut *mut XdgTrash) -> XdgTrashStatus {
+    if out.is_null() {
+        return invalid_argument("out is null");
+    }
+    guard(|| match UnifiedTrash::new() {
+        Ok(inner) => {
+            out.write(Box::into_raw(Box::new(XdgTrash { inner })));
+            XdgTrashStatus::Ok
+        }
+        Err(e) => set_error(&e),
+    })
+}
+
+/// Creates a handle that only knows the home trash of the current user.
+///
+/// Listing and emptying only affect the home trash. Putting a file from another
+/// filesystem still finds or creates a trash on it, like [`xdg_trash_new`].
+///
+/// # Safety
+/// `out` must be a valid pointer, the handle written to it must be freed with [`xdg_trash_free`].
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_new_home(out: *mut *mut XdgTrash) -> XdgTrashStatus {
+    if out.is_null() {
+        return invalid_argument("out is null");
+    }
+    guard(|| match Trash::find_home_trash() {
+        Ok(home) => {
+            let inner = UnifiedTrash::with_trashcans([Rc::new(home)].into_iter());
+            out.write(Box::into_raw(Box::new(XdgTrash { inner })));
+            XdgTrashStatus::Ok
+        }
+        Err(e) => set_error(&e),
+    })
+}
+
+/// Frees a handle created by [`xdg_trash_new`] or [`xdg_trash_new_home`], null is ignored.
+///
+/// # Safety
+/// `trash` must be null or a handle that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_free(trash: *mut XdgTrash) {
+    if !trash.is_null() {
+        drop(Box::from_raw(trash));
+    }
+}
+
+/// Puts the file at `path` into the trash, see [`UnifiedTrash::put`].
+///
+/// If `out` is not null, the created entry is written to it.
+///
+/// # Safety
+/// `trash` must be a valid handle and `path` a nul-terminated string.
+/// `out` must be null or a valid pointer, the entry written to it must be freed with [`xdg_trash_entry_free`].
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_put(
+    trash: *mut XdgTrash,
+    path: *const c_char,
+    out: *mut *mut XdgTrashEntry,
+) -> XdgTrashStatus {
+    let (Some(trash), Some(path)) = (trash.as_mut(), path_arg(path)) else {
+        return invalid_argument("trash or path is null");
+    };
+    guard(|| match trash.inner.put(path) {
+        Ok(file) => {
+            write_opt(out, Box::into_raw(Box::new(XdgTrashEntry::new(file))));
+            XdgTrashStatus::Ok
+        }
+        Err(e) => set_error(&e),
+    })
+}
+
+/// Lists all entries of all trashcans. The entries are read up front,
+/// later changes to the trash are not reflected by the iterator.
+///
+/// # Safety
+/// `trash` must be a valid handle and `out` a valid pointer,
+/// the iterator written to it must be freed with [`xdg_trash_iter_free`].
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_list(
+    trash: *const XdgTrash,
+    out: *mut *mut XdgTrashIter,
+) -> XdgTrashStatus {
+    let Some(trash) = trash.as_ref() else {
+        return invalid_argument("trash is null");
+    };
+    if out.is_null() {
+        return invalid_argument("out is null");
+    }
+    guard(|| {
+        let entries = trash.inner.list().collect::<Vec<_>>().into_iter();
+        out.write(Box::into_raw(Box::new(XdgTrashIter { entries })));
+        XdgTrashStatus::Ok
+    })
+}
+
+/// Advances the iterator, writing the next entry to `out`.
+///
+/// At the end, `XDG_TRASH_STATUS_OK` is returned and null is written to `out`.
+/// Broken entries return an error and write null, iteration can continue after them.
+///
+/// # Safety
+/// `iter` must be a valid iterator and `out` a valid pointer,
+/// the entry written to it must be freed with [`xdg_trash_entry_free`].
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_iter_next(
+    iter: *mut XdgTrashIter,
+    out: *mut *mut XdgTrashEntry,
+) -> XdgTrashStatus {
+    let Some(iter) = iter.as_mut() else {
+        return invalid_argument("iter is null");
+    };
+    if out.is_null() {
+        return invalid_argument("out is null");
+    }
+    out.write(ptr::null_mut());
+    guard(|| match iter.entries.next() {
+        Some(Ok(file)) => {
+            out.write(Box::into_raw(Box::new(XdgTrashEntry::new(file))));
+            XdgTrashStatus::Ok
+        }
+        Some(Err(e)) => set_error(&e),
+        None => XdgTrashStatus::Ok,
+    })
+}
+
+/// Frees an iterator created by [`xdg_trash_list`], null is ignored.
+/// Entries returned by it stay valid.
+///
+/// # Safety
+/// `iter` must be null or an iterator that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_iter_free(iter: *mut XdgTrashIter) {
+    if !iter.is_null() {
+        drop(Box::from_raw(iter));
+    }
+}
+
+/// The short ID of the entry, see [`TrashFile::id`]. Returns null if `entry` is null.
+///
+/// # Safety
+/// `entry` must be null or a valid entry, the string is valid as long as the entry.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_entry_id(entry: *const XdgTrashEntry) -> *const c_char {
+    entry.as_ref().map_or(ptr::null(), |x| x.id.as_ptr())
+}
+
+/// The location the entry is restored to. Returns null if `entry` is null.
+///
+/// # Safety
+/// `entry` must be null or a valid entry, the string is valid as long as the entry.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_entry_original_path(
+    entry: *const XdgTrashEntry,
+) -> *const c_char {
+    entry
+        .as_ref()
+        .map_or(ptr::null(), |x| x.original_path.as_ptr())
+}
+
+/// The path of the entries `.trashinfo` file. Returns null if `entry` is null.
+///
+/// # Safety
+/// `entry` must be null or a valid entry, the string is valid as long as the entry.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_entry_info_path(entry: *const XdgTrashEntry) -> *const c_char {
+    entry.as_ref().map_or(ptr::null(), |x| x.info_path.as_ptr())
+}
+
+/// The path of the trashed file inside the trash. Returns null if `entry` is null.
+///
+/// # Safety
+/// `entry` must be null or a valid entry, the string is valid as long as the entry.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_entry_files_path(entry: *const XdgTrashEntry) -> *const c_char {
+    entry
+        .as_ref()
+        .map_or(ptr::null(), |x| x.files_path.as_ptr())
+}
+
+/// The time the entry was trashed as a unix timestamp, assuming the
+/// `.trashinfo` file uses local time. Returns 0 if `entry` is null.
+///
+/// # Safety
+/// `entry` must be null or a valid entry.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_entry_deleted_at(entry: *const XdgTrashEntry) -> i64 {
+    entry.as_ref().map_or(0, |x| x.deleted_at)
+}
+
+/// Restores the entry to it's original location, see [`TrashFile::restore`].
+///
+/// `options` may be null to use the defaults. If `out_path` is not null, the restored
+/// path is written to it and must be freed with [`xdg_trash_string_free`].
+/// Once restored, the entry can only be freed, other operations fail.
+///
+/// # Safety
+/// `entry` must be a valid entry, `options` and `out_path` must be null or valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_entry_restore(
+    entry: *mut XdgTrashEntry,
+    options: *const XdgTrashRestoreOptions,
+    out_path: *mut *mut c_char,
+) -> XdgTrashStatus {
+    let Some(entry) = entry.as_mut() else {
+        return invalid_argument("entry is null");
+    };
+    let options = options.as_ref().copied().unwrap_or_default();
+    let Some(file) = entry.file.take() else {
+        return invalid_argument("the entry was already restored or removed");
+    };
+    guard(|| match file.restore(options.overwrite) {
+        Ok(path) => {
+            write_opt(out_path, c_string(path.as_os_str().as_bytes()).into_raw());
+            XdgTrashStatus::Ok
+        }
+        Err((file, e)) => {
+            entry.file = Some(file);
+            set_error(&e)
+        }
+    })
+}
+
+/// Permanently removes the entry from the trash, see [`TrashFile::remove`].
+/// Once removed, the entry can only be freed, other operations fail.
+///
+/// # Safety
+/// `entry` must be a valid entry.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_entry_remove(entry: *mut XdgTrashEntry) -> XdgTrashStatus {
+    let Some(entry) = entry.as_mut() else {
+        return invalid_argument("entry is null");
+    };
+    let Some(file) = entry.file.take() else {
+        return invalid_argument("the entry was already restored or removed");
+    };
+    guard(|| match file.remove() {
+        Ok(()) => XdgTrashStatus::Ok,
+        Err((file, e)) => {
+            entry.file = Some(file);
+            set_error(&e)
+        }
+    })
+}
+
+/// Frees an entry, null is ignored.
+///
+/// # Safety
+/// `entry` must be null or an entry that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_entry_free(entry: *mut XdgTrashEntry) {
+    if !entry.is_null() {
+        drop(Box::from_raw(entry));
+    }
+}
+
+/// Permanently removes all entries from all trashcans, see [`UnifiedTrash::empty`].
+///
+/// The amount of removed and failed entries and the freed bytes are written to the
+/// out-parameters that are not null. If some entries could not be removed,
+/// `XDG_TRASH_STATUS_PARTIAL_FAILURE` is returned and the last error message
+/// describes the first failure.
+///
+/// # Safety
+/// `trash` must be a valid handle, the out-parameters must be null or valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_empty(
+    trash: *mut XdgTrash,
+    out_removed: *mut u64,
+    out_failed: *mut u64,
+    out_bytes_freed: *mut u64,
+) -> XdgTrashStatus {
+    let Some(trash) = trash.as_ref() else {
+        return invalid_argument("trash is null");
+    };
+    guard(|| {
+        let report = trash.inner.empty();
+        write_opt(out_removed, report.removed.len() as u64);
+        write_opt(out_failed, report.failed.len() as u64);
+        write_opt(out_bytes_freed, report.bytes_freed);
+        match report.failed.first() {
+            Some((path, e)) => {
+                set_message(&format!("Failed to remove '{}': {}", path.display(), e));
+                XdgTrashStatus::PartialFailure
+            }
+            None => XdgTrashStatus::Ok,
+        }
+    })
+}
+
+/// Frees a string returned through an out-parameter, null is ignored.
+///
+/// # Safety
+/// `s` must be null or a string returned by this library that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn xdg_trash_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
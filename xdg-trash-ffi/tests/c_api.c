@@ -0,0 +1,133 @@
+/* Exercises the C API, compiled and run by tests/c_api.rs with a scratch HOME */
+
+#include <stdio.h>
+#include <string.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "xdg_trash.h"
+
+#define CHECK(cond)                                                            \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            const char *msg = xdg_trash_last_error_message();                  \
+            fprintf(stderr, "%s:%d: check failed: %s (last error: %s)\n",      \
+                    __FILE__, __LINE__, #cond, msg ? msg : "none");            \
+            return 1;                                                          \
+        }                                                                      \
+    } while (0)
+
+static int create_file(const char *path) {
+    FILE *f = fopen(path, "w");
+    if (!f) {
+        return 0;
+    }
+    fputs("trash me\n", f);
+    return fclose(f) == 0;
+}
+
+/* Finds the entry with the given original path, returns NULL if there is none */
+static XdgTrashEntry *find_entry(XdgTrash *trash, const char *path) {
+    XdgTrashIter *iter = NULL;
+    XdgTrashEntry *found = NULL;
+    if (xdg_trash_list(trash, &iter) != XDG_TRASH_STATUS_OK) {
+        return NULL;
+    }
+    for (;;) {
+        XdgTrashEntry *entry = NULL;
+        XdgTrashStatus status = xdg_trash_iter_next(iter, &entry);
+        if (status != XDG_TRASH_STATUS_OK) {
+            continue;
+        }
+        if (!entry) {
+            break;
+        }
+        if (!found && strcmp(xdg_trash_entry_original_path(entry), path) == 0) {
+            found = entry;
+        } else {
+            xdg_trash_entry_free(entry);
+        }
+    }
+    xdg_trash_iter_free(iter);
+    return found;
+}
+
+int main(void) {
+    const char *home = getenv("HOME");
+    CHECK(home != NULL);
+
+    char path[4096];
+    snprintf(path, sizeof path, "%s/c file.txt", home);
+
+    XdgTrash *all = NULL;
+    CHECK(xdg_trash_new(&all) == XDG_TRASH_STATUS_OK);
+    CHECK(all != NULL);
+    xdg_trash_free(all);
+
+    /* Only the home trash, so emptying never touches trashes on other filesystems */
+    XdgTrash *trash = NULL;
+    CHECK(xdg_trash_new_home(&trash) == XDG_TRASH_STATUS_OK);
+    CHECK(trash != NULL);
+
+    /* put */
+    CHECK(create_file(path));
+    XdgTrashEntry *entry = NULL;
+    CHECK(xdg_trash_put(trash, path, &entry) == XDG_TRASH_STATUS_OK);
+    CHECK(access(path, F_OK) != 0);
+    CHECK(strcmp(xdg_trash_entry_original_path(entry), path) == 0);
+    CHECK(strlen(xdg_trash_entry_id(entry)) == 10);
+    CHECK(access(xdg_trash_entry_files_path(entry), F_OK) == 0);
+    CHECK(access(xdg_trash_entry_info_path(entry), F_OK) == 0);
+    long long age = (long long)time(NULL) - (long long)xdg_trash_entry_deleted_at(entry);
+    CHECK(age >= -5 && age <= 60);
+    xdg_trash_entry_free(entry);
+
+    /* list and restore */
+    entry = find_entry(trash, path);
+    CHECK(entry != NULL);
+    char *restored = NULL;
+    CHECK(xdg_trash_entry_restore(entry, NULL, &restored) == XDG_TRASH_STATUS_OK);
+    CHECK(strcmp(restored, path) == 0);
+    CHECK(access(path, F_OK) == 0);
+    xdg_trash_string_free(restored);
+    CHECK(xdg_trash_entry_restore(entry, NULL, NULL) == XDG_TRASH_STATUS_INVALID_ARGUMENT);
+    xdg_trash_entry_free(entry);
+
+    /* restoring over an existing file needs the overwrite option */
+    CHECK(xdg_trash_put(trash, path, NULL) == XDG_TRASH_STATUS_OK);
+    CHECK(create_file(path));
+    entry = find_entry(trash, path);
+    CHECK(entry != NULL);
+    CHECK(xdg_trash_entry_restore(entry, NULL, NULL) == XDG_TRASH_STATUS_CONFLICT);
+    CHECK(xdg_trash_last_error_message() != NULL);
+    XdgTrashRestoreOptions options = {.overwrite = true};
+    CHECK(xdg_trash_entry_restore(entry, &options, NULL) == XDG_TRASH_STATUS_OK);
+    xdg_trash_entry_free(entry);
+
+    /* remove */
+    CHECK(xdg_trash_put(trash, path, &entry) == XDG_TRASH_STATUS_OK);
+    CHECK(xdg_trash_entry_remove(entry) == XDG_TRASH_STATUS_OK);
+    CHECK(access(xdg_trash_entry_files_path(entry), F_OK) != 0);
+    CHECK(xdg_trash_entry_remove(entry) == XDG_TRASH_STATUS_INVALID_ARGUMENT);
+    xdg_trash_entry_free(entry);
+
+    /* empty */
+    CHECK(create_file(path));
+    CHECK(xdg_trash_put(trash, path, NULL) == XDG_TRASH_STATUS_OK);
+    uint64_t removed = 0, failed = 0;
+    CHECK(xdg_trash_empty(trash, &removed, &failed, NULL) == XDG_TRASH_STATUS_OK);
+    CHECK(removed >= 1 && failed == 0);
+    CHECK(find_entry(trash, path) == NULL);
+
+    /* errors */
+    snprintf(path, sizeof path, "%s/does not exist", home);
+    CHECK(xdg_trash_put(trash, path, NULL) == XDG_TRASH_STATUS_NOT_FOUND);
+    CHECK(strstr(xdg_trash_last_error_message(), "does not exist") != NULL);
+    CHECK(xdg_trash_put(trash, NULL, NULL) == XDG_TRASH_STATUS_INVALID_ARGUMENT);
+    CHECK(xdg_trash_new(NULL) == XDG_TRASH_STATUS_INVALID_ARGUMENT);
+    CHECK(strcmp(xdg_trash_status_str(XDG_TRASH_STATUS_CONFLICT),
+                 "a file already exists at the destination") == 0);
+
+    xdg_trash_free(trash);
+    return 0;
+}
@@ -0,0 +1,78 @@
+use std::{env, fs, path::PathBuf, process::Command};
+use tempdir::TempDir;
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+#[test]
+fn header_is_up_to_date() {
+    let dir = manifest_dir();
+    let config = cbindgen::Config::from_file(dir.join("cbindgen.toml")).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(&dir)
+        .with_config(config)
+        .generate()
+        .unwrap()
+        .write(&mut generated);
+
+    let header = dir.join("include/xdg_trash.h");
+    if env::var_os("XDG_TRASH_BLESS").is_some() {
+        fs::write(&header, &generated).unwrap();
+    }
+    assert!(
+        fs::read(&header).unwrap() == generated,
+        "include/xdg_trash.h is outdated, regenerate it with XDG_TRASH_BLESS=1"
+    );
+}
+
+#[test]
+fn c_program() {
+    let dir = manifest_dir();
+
+    // Cargo only builds the rlib for integration tests, so the shared library could be outdated
+    let mut build = Command::new(env!("CARGO"));
+    build
+        .args(["build", "--lib", "--manifest-path"])
+        .arg(dir.join("Cargo.toml"));
+    if !cfg!(debug_assertions) {
+        build.arg("--release");
+    }
+    assert!(build.status().unwrap().success());
+
+    // The test binary lives in target/<profile>/deps, next to the libraries
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap().parent().unwrap();
+
+    let tmp = TempDir::new("xdg_trash_ffi_test").unwrap();
+    let program = tmp.path().join("c_api");
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_owned()))
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-o"])
+        .arg(&program)
+        .arg(dir.join("tests/c_api.c"))
+        .arg("-I")
+        .arg(dir.join("include"))
+        .arg("-L")
+        .arg(lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lxdg_trash_ffi")
+        .status()
+        .unwrap();
+    assert!(status.success(), "failed to compile the C test program");
+
+    // A scratch home directory, so the users actual trash is never touched
+    let home = tmp.path().join("home");
+    fs::create_dir(&home).unwrap();
+    let output = Command::new(&program)
+        .env("HOME", &home)
+        .env_remove("XDG_DATA_HOME")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "C test program failed:\n{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,241 @@
+//! Thin wrappers around the `*at` family of syscalls.
+//!
+//! Resolving paths relative to an open directory instead of from the root means that
+//! components that were already checked can't be swapped for symlinks afterwards.
+//! All functions here never follow a symlink in the final component, unless stated otherwise.
+
+use std::{
+    ffi::{CStr, CString, OsString},
+    fs::File,
+    io,
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+        unix::ffi::{OsStrExt, OsStringExt},
+    },
+    path::Path,
+};
+
+/// The directory relative paths are resolved against
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Dir<'a> {
+    /// The current working directory, absolute paths work as usual
+    Cwd,
+    /// An open directory
+    Fd(BorrowedFd<'a>),
+}
+
+impl Dir<'_> {
+    fn raw(self) -> RawFd {
+        match self {
+            Self::Cwd => libc::AT_FDCWD,
+            Self::Fd(fd) => fd.as_raw_fd(),
+        }
+    }
+}
+
+fn cstr(path: &Path) -> io::Result<CString> {
+    Ok(CString::new(path.as_os_str().as_bytes())?)
+}
+
+fn cvt(res: libc::c_int) -> io::Result<libc::c_int> {
+    if res == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(res)
+    }
+}
+
+/// Opens the directory at `path`, following a symlink in the final component only if `follow` is set
+pub(crate) fn open_dir_at(dir: Dir, path: &Path, follow: bool) -> io::Result<OwnedFd> {
+    let mut flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+    if !follow {
+        flags |= libc::O_NOFOLLOW;
+    }
+    let fd = cvt(unsafe { libc::openat(dir.raw(), cstr(path)?.as_ptr(), flags) })?;
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// Opens the file at `path` with the given flags, `mode` is used if a file is created
+pub(crate) fn open_file_at(
+    dir: Dir,
+    path: &Path,
+    flags: libc::c_int,
+    mode: libc::mode_t,
+) -> io::Result<File> {
+    let flags = flags | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+    let fd = cvt(unsafe {
+        libc::openat(
+            dir.raw(),
+            cstr(path)?.as_ptr(),
+            flags,
+            libc::c_uint::from(mode),
+        )
+    })?;
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+pub(crate) fn mkdir_at(dir: Dir, path: &Path, mode: libc::mode_t) -> io::Result<()> {
+    cvt(unsafe { libc::mkdirat(dir.raw(), cstr(path)?.as_ptr(), mode) }).map(drop)
+}
+
+pub(crate) fn unlink_at(dir: Dir, path: &Path) -> io::Result<()> {
+    cvt(unsafe { libc::unlinkat(dir.raw(), cstr(path)?.as_ptr(), 0) }).map(drop)
+}
+
+pub(crate) fn rmdir_at(dir: Dir, path: &Path) -> io::Result<()> {
+    cvt(unsafe { libc::unlinkat(dir.raw(), cstr(path)?.as_ptr(), libc::AT_REMOVEDIR) }).map(drop)
+}
+
+/// Changes the owner of `path`, keeping the group if `gid` is `None`
+pub(crate) fn chown_at(dir: Dir, path: &Path, uid: u32, gid: Option<u32>) -> io::Result<()> {
+    cvt(unsafe {
+        libc::fchownat(
+            dir.raw(),
+            cstr(path)?.as_ptr(),
+            uid,
+            gid.unwrap_or(libc::gid_t::MAX),
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    })
+    .map(drop)
+}
+
+/// Plain `renameat`, replacing `to` if it exists
+pub(crate) fn rename_at(from_dir: Dir, from: &Path, to_dir: Dir, to: &Path) -> io::Result<()> {
+    cvt(unsafe {
+        libc::renameat(
+            from_dir.raw(),
+            cstr(from)?.as_ptr(),
+            to_dir.raw(),
+            cstr(to)?.as_ptr(),
+        )
+    })
+    .map(drop)
+}
+
+/// Like `renameat`, but fails if `to` exists. Fails with `EINVAL` if the filesystem
+/// doesn't support this and with `ENOSYS` if the kernel doesn't.
+pub(crate) fn renameat2_noreplace(
+    from_dir: Dir,
+    from: &Path,
+    to_dir: Dir,
+    to: &Path,
+) -> io::Result<()> {
+    let from = cstr(from)?;
+    let to = cstr(to)?;
+
+    // Through the syscall directly, as not every libc has a wrapper for it
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_renameat2,
+            from_dir.raw(),
+            from.as_ptr(),
+            to_dir.raw(),
+            to.as_ptr(),
+            libc::RENAME_NOREPLACE,
+        )
+    };
+    if res == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Creates a hard link, never following symlinks
+pub(crate) fn link_at(from_dir: Dir, from: &Path, to_dir: Dir, to: &Path) -> io::Result<()> {
+    cvt(unsafe {
+        libc::linkat(
+            from_dir.raw(),
+            cstr(from)?.as_ptr(),
+            to_dir.raw(),
+            cstr(to)?.as_ptr(),
+            0,
+        )
+    })
+    .map(drop)
+}
+
+/// `lstat` relative to `dir`
+pub(crate) fn stat_at(dir: Dir, path: &Path) -> io::Result<libc::stat> {
+    let mut stat = unsafe { std::mem::zeroed() };
+    cvt(unsafe {
+        libc::fstatat(
+            dir.raw(),
+            cstr(path)?.as_ptr(),
+            &mut stat,
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    })?;
+    Ok(stat)
+}
+
+pub(crate) fn fstat(fd: BorrowedFd) -> io::Result<libc::stat> {
+    let mut stat = unsafe { std::mem::zeroed() };
+    cvt(unsafe { libc::fstat(fd.as_raw_fd(), &mut stat) })?;
+    Ok(stat)
+}
+
+pub(crate) fn fchmod(fd: BorrowedFd, mode: libc::mode_t) -> io::Result<()> {
+    cvt(unsafe { libc::fchmod(fd.as_raw_fd(), mode) }).map(drop)
+}
+
+pub(crate) fn is_dir(stat: &libc::stat) -> bool {
+    stat.st_mode & libc::S_IFMT == libc::S_IFDIR
+}
+
+/// Lists the names of all entries in the directory, without `.` and `..`
+pub(crate) fn list_dir(fd: BorrowedFd) -> io::Result<Vec<OsString>> {
+    let dup = cvt(unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_DUPFD_CLOEXEC, 0) })?;
+    let dirp = unsafe { libc::fdopendir(dup) };
+    if dirp.is_null() {
+        let e = io::Error::last_os_error();
+        unsafe { libc::close(dup) };
+        return Err(e);
+    }
+    // The duplicate shares its position with `fd`
+    unsafe { libc::rewinddir(dirp) };
+
+    let mut names = Vec::new();
+    let result = loop {
+        unsafe { *libc::__errno_location() = 0 };
+        let entry = unsafe { libc::readdir(dirp) };
+        if entry.is_null() {
+            let e = io::Error::last_os_error();
+            break if e.raw_os_error() == Some(0) {
+                Ok(names)
+            } else {
+                Err(e)
+            };
+        }
+
+        let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) }.to_bytes();
+        if name != b"." && name != b".." {
+            names.push(OsString::from_vec(name.to_vec()));
+        }
+    };
+
+    unsafe { libc::closedir(dirp) };
+    result
+}
+
+/// Removes `path` and, if it's a directory, everything below it, never following symlinks
+pub(crate) fn remove_tree_at(dir: BorrowedFd, path: &Path) -> io::Result<()> {
+    // Linux reports EISDIR for directories, but POSIX allows EPERM as well
+    let unlink_err = match unlink_at(Dir::Fd(dir), path) {
+        Err(e) if matches!(e.raw_os_error(), Some(libc::EISDIR | libc::EPERM)) => e,
+        other => return other,
+    };
+
+    let sub = match open_dir_at(Dir::Fd(dir), path, false) {
+        Err(e) if e.raw_os_error() == Some(libc::ENOTDIR) => return Err(unlink_err),
+        other => other?,
+    };
+    for name in list_dir(sub.as_fd())? {
+        match remove_tree_at(sub.as_fd(), Path::new(&name)) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            other => other?,
+        }
+    }
+    rmdir_at(Dir::Fd(dir), path)
+}
@@ -0,0 +1,153 @@
+use crate::{mounts::list_mounts, trash::user_trash_dir, Trash, TrashType};
+use std::{env, fmt::Display, path::PathBuf, rc::Rc};
+
+/// A detailed report of how trashcans were discovered, see [`discover`]
+#[derive(Debug)]
+pub struct DiscoveryReport {
+    /// How the home trash was resolved
+    pub home: HomeResolution,
+    /// Every mounted filesystem that was examined, in the order of the mount table
+    pub mounts: Vec<MountReport>,
+}
+
+/// How the home trash was resolved
+#[derive(Debug)]
+pub struct HomeResolution {
+    /// The value of `$HOME`, if set
+    pub home_dir: Option<PathBuf>,
+    /// The value of `$XDG_DATA_HOME`, if set. Otherwise `$HOME/.local/share` is used.
+    pub xdg_data_home: Option<PathBuf>,
+    /// The home trash, or the reason it couldn't be determined
+    pub result: crate::Result<Rc<Trash>>,
+}
+
+/// All candidate trash directories examined on a single mounted filesystem
+#[derive(Debug)]
+pub struct MountReport {
+    /// Where the filesystem is mounted
+    pub mount_root: PathBuf,
+    /// The candidate trash directories, in the order of their priority
+    pub candidates: Vec<Candidate>,
+}
+
+/// A directory that could be a trash
+#[derive(Debug)]
+pub struct Candidate {
+    /// The directory that was examined
+    pub path: PathBuf,
+    /// The type of trash this would be
+    pub trash_type: TrashType,
+    /// Whether the directory is used as a trash
+    pub status: CandidateStatus,
+}
+
+/// Whether a [`Candidate`] is used as a trash
+#[derive(Debug)]
+pub enum CandidateStatus {
+    /// The directory is used as a trash
+    Accepted(Rc<Trash>),
+    /// The directory is not used as a trash
+    Rejected(RejectReason),
+}
+
+/// The reason a [`Candidate`] was rejected
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum RejectReason {
+    /// The directory doesn't exist
+    Missing,
+    /// The admin trash doesn't have the sticky bit set
+    NotSticky,
+    /// The admin trash is a symlink
+    IsSymlink,
+    /// The directory could not be examined
+    Error(crate::Error),
+}
+
+impl Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing => write!(f, "does not exist"),
+            Self::NotSticky => write!(f, "the sticky bit is not set"),
+            Self::IsSymlink => write!(f, "it is a symlink"),
+            Self::Error(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<crate::Error> for RejectReason {
+    fn from(e: crate::Error) -> Self {
+        match e {
+            crate::Error::NotSticky(_) => Self::NotSticky,
+            crate::Error::IsSymlink(_) => Self::IsSymlink,
+            e if e.kind() == crate::ErrorKind::NotFound => Self::Missing,
+            e => Self::Error(e),
+        }
+    }
+}
+
+impl MountReport {
+    /// Returns all accepted trashes on this filesystem, in the order of their priority.
+    ///
+    /// All of them are listed, files are put into the first one.
+    pub fn accepted(&self) -> impl Iterator<Item = &Rc<Trash>> {
+        self.candidates.iter().filter_map(|x| match &x.status {
+            CandidateStatus::Accepted(trash) => Some(trash),
+            CandidateStatus::Rejected(_) => None,
+        })
+    }
+}
+
+/// Discovers all trashcans like [`crate::list_trashes`], but reports every mount that was examined,
+/// every candidate trash directory and why it was accepted or rejected.
+///
+/// This is meant for diagnosing why files end up in a certain trash.
+pub fn discover() -> crate::Result<DiscoveryReport> {
+    let _span = span!("discover");
+    let home = HomeResolution {
+        home_dir: env::var_os("HOME").map(PathBuf::from),
+        xdg_data_home: env::var_os("XDG_DATA_HOME").map(PathBuf::from),
+        result: Trash::find_home_trash().map(Rc::new),
+    };
+
+    let uid = unsafe { libc::getuid() };
+    let mounts = list_mounts()?
+        .into_iter()
+        .map(|x| examine_mount(x, uid))
+        .collect();
+
+    Ok(DiscoveryReport { home, mounts })
+}
+
+/// Examines every candidate trash directory of the user `uid` on the filesystem mounted at `mount_root`
+pub(crate) fn examine_mount(mount_root: PathBuf, uid: u32) -> MountReport {
+    let _span = span!(
+        "examine_mount",
+        mount_root = mount_root.display(),
+        uid = uid
+    );
+    let candidates = vec![
+        Candidate {
+            path: mount_root.join(".Trash"),
+            trash_type: TrashType::Admin,
+            status: status_of(Trash::find_admin_trash_of(mount_root.clone(), uid)),
+        },
+        Candidate {
+            path: user_trash_dir(&mount_root, uid),
+            trash_type: TrashType::User,
+            status: status_of(Trash::find_user_trash_of(mount_root.clone(), uid)),
+        },
+    ];
+
+    MountReport {
+        mount_root,
+        candidates,
+    }
+}
+
+fn status_of(result: crate::Result<Trash>) -> CandidateStatus {
+    match result {
+        Ok(trash) => CandidateStatus::Accepted(Rc::new(trash)),
+        Err(e) => CandidateStatus::Rejected(e.into()),
+    }
+}
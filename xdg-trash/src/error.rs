@@ -1,5 +1,8 @@
 use displaydoc::Display;
-use std::{io, path::PathBuf};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 
 /// Error type representing every possible error in this crate
@@ -10,7 +13,29 @@ pub enum Error {
     Homeless,
 
     /** Io Error: {0} */
-    IoError(#[from] io::Error),
+    IoError(#[source] io::Error),
+
+    /** Failed to {op} '{path}': {source} */
+    Fs {
+        /** The operation that failed */
+        op: FsOp,
+        /** The path the operation was performed on */
+        path: PathBuf,
+        /** The underlying error */
+        #[source]
+        source: io::Error,
+    },
+
+    /** Failed to move '{from}' to '{to}': {source} */
+    Rename {
+        /** The path that was moved */
+        from: PathBuf,
+        /** The destination it was moved to */
+        to: PathBuf,
+        /** The underlying error */
+        #[source]
+        source: io::Error,
+    },
 
     /** Failed to determine the home trash */
     FailedToFindHomeTrash(#[source] Box<Self>),
@@ -21,9 +46,24 @@ pub enum Error {
     /** The trash at '{0}' is invalid because it is a symlink */
     IsSymlink(PathBuf),
 
+    /** '{0}' is not a directory */
+    NotADirectory(PathBuf),
+
+    /** The trash at '{0}' is not owned by the user it belongs to */
+    NotOwned(PathBuf),
+
+    /** The user '{0}' does not exist */
+    UnknownUser(String),
+
+    /** Failed to read the user database: {0} */
+    UserLookup(#[source] io::Error),
+
     /** The /proc/mounts file was not found or in an unexpected format */
     InvalidProcMounts,
 
+    /** The file is not valid UTF-8: {0} */
+    InvalidUtf8(#[source] std::str::Utf8Error),
+
     /** The first line was invalid */
     InvalidFirstLine,
 
@@ -33,6 +73,12 @@ pub enum Error {
     /** The key {0} was not found */
     MissingKey(&'static str),
 
+    /** '{0}' is not a valid extension key, they must start with 'X-' and can't contain '=' or line breaks */
+    InvalidExtraKey(String),
+
+    /** The value of the extension key '{0}' contains a line break */
+    InvalidExtraValue(String),
+
     /** The datetime was invalid: {0} */
     InvalidDateTime(#[from] chrono::ParseError),
 
@@ -69,9 +115,6 @@ pub enum Error {
      */
     InputNotChildOfTrashMount,
 
-    /** Failed to move file: {0} */
-    FailedToMoveFile(std::io::Error),
-
     /** Failed to create a new trashcan at '{0}' */
     FailedToCreateTrash(PathBuf, #[source] Box<Self>),
 
@@ -83,7 +126,184 @@ pub enum Error {
 
     /** Failed to trash file '{0}': {1} */
     FailedToDeleteFile(PathBuf, #[source] Box<Self>),
+
+    /** The operation was cancelled */
+    Cancelled,
 }
 
 /// A Result type predefined with this librarys error
 pub type Result<T> = core::result::Result<T, Error>;
+
+/// A filesystem operation, attached to io errors to tell what exactly failed
+#[non_exhaustive]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FsOp {
+    /** read metadata of */
+    Stat,
+    /** read directory */
+    ReadDir,
+    /** create directory */
+    CreateDir,
+    /** open */
+    Open,
+    /** create */
+    Create,
+    /** read */
+    Read,
+    /** write */
+    Write,
+    /** remove file */
+    RemoveFile,
+    /** remove directory */
+    RemoveDir,
+    /** get current directory */
+    CurrentDir,
+    /** resolve the path of */
+    Resolve,
+    /** lock */
+    Lock,
+    /** change the owner of */
+    Chown,
+    /** change the permissions of */
+    Chmod,
+}
+
+/// A coarse classification of errors, see [`Error::kind`]
+#[non_exhaustive]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /** permission denied */
+    PermissionDenied,
+    /** not found */
+    NotFound,
+    /** read-only filesystem */
+    ReadOnlyFilesystem,
+    /** cross-device operation */
+    CrossDevice,
+    /** a file already exists at the destination */
+    Conflict,
+    /** invalid trashinfo file */
+    InvalidTrashinfo,
+    /** cancelled */
+    Cancelled,
+    /** other error */
+    Other,
+}
+
+impl Error {
+    /// Classifies this error, looking through all wrapping errors (like [`Error::FailedToTrashFile`])
+    /// at the error that actually caused it.
+    ///
+    /// # Example
+    /// ```
+    /// use xdg_trash::{ErrorKind, UnifiedTrash};
+    ///
+    /// let mut trash = UnifiedTrash::new().unwrap();
+    /// match trash.put("/this/does/not/exist") {
+    ///     Err(e) if e.kind() == ErrorKind::NotFound => println!("Nothing to trash"),
+    ///     other => panic!("{other:?}"),
+    /// }
+    /// ```
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self.root_cause() {
+            Self::IoError(e)
+            | Self::UserLookup(e)
+            | Self::Fs { source: e, .. }
+            | Self::Rename { source: e, .. } => classify_io(e),
+            Self::AlreadyExists(_) => ErrorKind::Conflict,
+            Self::DifferentDevice => ErrorKind::CrossDevice,
+            Self::UnknownUser(_) => ErrorKind::NotFound,
+            Self::Cancelled => ErrorKind::Cancelled,
+            Self::InvalidUtf8(_)
+            | Self::InvalidFirstLine
+            | Self::InvalidKeyValues
+            | Self::MissingKey(_)
+            | Self::InvalidExtraKey(_)
+            | Self::InvalidExtraValue(_)
+            | Self::InvalidDateTime(_)
+            | Self::InvalidDateTimeNoParserMatched { .. }
+            | Self::InvalidTrashinfoExt
+            | Self::HasNoFileStem(_)
+            | Self::OrphanedTrashinfoFile => ErrorKind::InvalidTrashinfo,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Returns the innermost error, unwrapping errors that only add context
+    #[must_use]
+    pub fn root_cause(&self) -> &Self {
+        match self {
+            Self::FailedToFindHomeTrash(inner)
+            | Self::InvalidTrashinfoFile(_, inner)
+            | Self::FailedToTrashFile(_, inner)
+            | Self::FailedToCreateTrash(_, inner)
+            | Self::FailedToDeleteFile(_, inner) => inner.root_cause(),
+            other => other,
+        }
+    }
+
+    /// The path involved in this error, if there is one.
+    ///
+    /// For wrapping errors, this is the most specific path available.
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Fs { path, .. } | Self::Rename { from: path, .. } => Some(path),
+            Self::NotSticky(path)
+            | Self::IsSymlink(path)
+            | Self::NotADirectory(path)
+            | Self::NotOwned(path)
+            | Self::HasNoFileStem(path)
+            | Self::AlreadyExists(path) => Some(path),
+            Self::InvalidTrashinfoFile(path, inner)
+            | Self::FailedToTrashFile(path, inner)
+            | Self::FailedToCreateTrash(path, inner)
+            | Self::FailedToDeleteFile(path, inner) => inner.path().or(Some(path)),
+            Self::FailedToFindHomeTrash(inner) => inner.path(),
+            _ => None,
+        }
+    }
+}
+
+fn classify_io(e: &io::Error) -> ErrorKind {
+    match e.raw_os_error() {
+        Some(libc::EACCES | libc::EPERM) => return ErrorKind::PermissionDenied,
+        Some(libc::ENOENT) => return ErrorKind::NotFound,
+        Some(libc::EROFS) => return ErrorKind::ReadOnlyFilesystem,
+        Some(libc::EXDEV) => return ErrorKind::CrossDevice,
+        Some(libc::EEXIST | libc::ENOTEMPTY) => return ErrorKind::Conflict,
+        _ => {}
+    }
+
+    match e.kind() {
+        io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+        io::ErrorKind::NotFound => ErrorKind::NotFound,
+        io::ErrorKind::AlreadyExists => ErrorKind::Conflict,
+        _ => ErrorKind::Other,
+    }
+}
+
+/// Attaches the failed operation and path to io errors
+pub(crate) trait IoResultExt<T> {
+    fn fs_err(self, op: FsOp, path: impl AsRef<Path>) -> Result<T>;
+    fn rename_err(self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<T>;
+}
+
+impl<T> IoResultExt<T> for io::Result<T> {
+    fn fs_err(self, op: FsOp, path: impl AsRef<Path>) -> Result<T> {
+        self.map_err(|source| Error::Fs {
+            op,
+            path: path.as_ref().to_owned(),
+            source,
+        })
+    }
+
+    fn rename_err(self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<T> {
+        self.map_err(|source| Error::Rename {
+            from: from.as_ref().to_owned(),
+            to: to.as_ref().to_owned(),
+            source,
+        })
+    }
+}
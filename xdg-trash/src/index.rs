@@ -0,0 +1,597 @@
+//! An optional on-disk index of a trashcans contents.
+//!
+//! The index lives next to the `info` and `files` directories and caches everything
+//! needed for lookups and sorting, so that large trashcans don't have to be fully parsed
+//! on every listing. It is only ever created by [`Trash::index`], all other operations
+//! (including [`Trash::existing_index`]) just keep an existing index up to date.
+//!
+//! ## Format
+//! The index is a line based, append-only log. Every field is percent-encoded, so the
+//! tab separator can never appear inside of a field.
+//! ```text
+//! xdg-trash-index 2\t<mount root>
+//! +\t<name>\t<mtime s>\t<mtime ns>\t<deleted at>\t<size or ->\t<id>\t<trashinfo path>\t<extension keys>
+//! -\t<name>
+//! C
+//! S\t<stamp>
+//! D\t<stamp before>\t<stamp after>
+//! ```
+//! Extension keys are stored as `key=value` lines, like in the `.trashinfo` file.
+//! `S` sets the state of the trash directories the index was last known to match, `D`
+//! records a change made by this crate. If the stamp before the change doesn't match,
+//! some other program changed the trash in the meantime and the index gets reconciled
+//! with the directories on the next load.
+
+use crate::{
+    dirfd,
+    error::{FsOp, IoResultExt},
+    trash::{Trash, TrashDirs, FILE_MODE},
+    trash_file::TrashFile,
+    trashinfo::TrashInfo,
+    ErrorKind,
+};
+use chrono::NaiveDateTime;
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    fs,
+    io::{Read, Write},
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{fchown, MetadataExt},
+    },
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+};
+
+const INDEX_FILENAME: &str = ".xdg-trash-index";
+const INDEX_TMP_FILENAME: &str = ".xdg-trash-index.tmp";
+const INDEX_HEADER: &str = "xdg-trash-index 2";
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// A snapshot of all entries in a single trashcan, loaded from the on-disk index.
+#[derive(Debug)]
+pub struct TrashIndex {
+    trash: Rc<Trash>,
+    entries: Vec<IndexEntry>,
+}
+
+/// A single cached entry of a [`TrashIndex`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexEntry {
+    trash: Rc<Trash>,
+    /// Filename WITHOUT .trashinfo ext
+    raw_filename: OsString,
+    info_mtime: (i64, i64),
+    trashinfo: TrashInfo,
+    size: Option<u64>,
+    id: String,
+}
+
+impl TrashIndex {
+    /// The trash this index belongs to
+    #[must_use]
+    pub fn trash(&self) -> &Trash {
+        &self.trash
+    }
+
+    /// All entries of this index, in no particular order
+    #[must_use]
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Consumes the index, returning all of it's entries
+    #[must_use]
+    pub fn into_entries(self) -> Vec<IndexEntry> {
+        self.entries
+    }
+
+    /// Returns all entries whose ID (see [`TrashFile::id`]) or original path matches `id_or_path`
+    pub fn find<'a>(&'a self, id_or_path: &'a str) -> impl Iterator<Item = &'a IndexEntry> {
+        self.entries
+            .iter()
+            .filter(move |x| x.id == id_or_path || x.original_path() == Path::new(id_or_path))
+    }
+}
+
+impl IndexEntry {
+    /// The location this entry will be moved to when restored
+    #[must_use]
+    pub fn original_path(&self) -> PathBuf {
+        self.trashinfo.original_path(&self.trash)
+    }
+
+    /// The time this item was moved into the trash
+    #[must_use]
+    pub fn deleted_at(&self) -> NaiveDateTime {
+        self.trashinfo.deleted_at()
+    }
+
+    /// The size on disk in bytes, if it was known at the time of indexing.
+    ///
+    /// Sizes are only calculated when the `fs_extra` feature is enabled.
+    #[must_use]
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// The ID of this entry, see [`TrashFile::id`]
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The trash this entry is in
+    #[must_use]
+    pub fn trash(&self) -> &Trash {
+        &self.trash
+    }
+
+    /// Converts this entry into a [`TrashFile`], without touching the filesystem.
+    ///
+    /// The index might be outdated if another program modified the trash after it was loaded,
+    /// in which case operations on the returned file will fail.
+    #[must_use]
+    pub fn into_trash_file(self) -> TrashFile {
+        #[allow(unused_mut)]
+        let mut file = TrashFile::new_unchecked(self.trash, self.trashinfo, self.raw_filename);
+        #[cfg(feature = "fs_extra")]
+        file.set_size(self.size);
+        file
+    }
+
+    fn from_trash_file(file: &TrashFile, info_mtime: (i64, i64)) -> Self {
+        Self {
+            trash: file.trash_rc(),
+            raw_filename: file.raw_filename().to_owned(),
+            info_mtime,
+            trashinfo: file.trashinfo().clone(),
+            size: calc_size(&file.files_filepath()),
+            id: file.id(),
+        }
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "+\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            encode(self.raw_filename.as_bytes()),
+            self.info_mtime.0,
+            self.info_mtime.1,
+            self.trashinfo.deleted_at().format(DATE_FORMAT),
+            self.size.map_or_else(|| "-".to_owned(), |x| x.to_string()),
+            self.id,
+            encode(self.trashinfo.path().as_os_str().as_bytes()),
+            encode(
+                self.trashinfo
+                    .extra()
+                    .iter()
+                    .map(|(k, v)| format!("{k}={v}\n"))
+                    .collect::<String>()
+                    .as_bytes()
+            ),
+        )
+    }
+
+    fn parse(fields: &[&str], trash: &Rc<Trash>) -> Option<Self> {
+        let [name, mtime_s, mtime_ns, deleted_at, size, id, path, extra] = fields else {
+            return None;
+        };
+
+        Some(Self {
+            trash: trash.clone(),
+            raw_filename: decode(name),
+            info_mtime: (mtime_s.parse().ok()?, mtime_ns.parse().ok()?),
+            trashinfo: decode(extra)
+                .to_str()?
+                .lines()
+                .map(|x| x.split_once('='))
+                .try_fold(
+                    TrashInfo::builder(decode(path))
+                        .deleted_at(NaiveDateTime::from_str(deleted_at).ok()?),
+                    |builder, kv| kv.map(|(k, v)| builder.extra(k, v)),
+                )?
+                .build()
+                .ok()?,
+            size: if *size == "-" {
+                None
+            } else {
+                Some(size.parse().ok()?)
+            },
+            id: (*id).to_owned(),
+        })
+    }
+}
+
+impl Trash {
+    /// Loads the on-disk index of this trash, creating it if it doesn't exist yet.
+    ///
+    /// If the trash was modified by another program since the index was last written,
+    /// only the changed entries are parsed again and the index gets rewritten.
+    /// Once an index exists, all operations of this crate keep it up to date.
+    ///
+    /// Failing to write the index (for example on a read-only filesystem) is not an error,
+    /// the index is then simply rebuilt in memory every time.
+    pub fn index(self: Rc<Self>) -> crate::Result<TrashIndex> {
+        self.load_index(true)
+    }
+
+    /// Like [`Trash::index`], but never creates a new index on disk.
+    ///
+    /// An existing index is used and kept up to date, otherwise the index is only built in memory.
+    pub fn existing_index(self: Rc<Self>) -> crate::Result<TrashIndex> {
+        self.load_index(false)
+    }
+
+    fn load_index(self: Rc<Self>, create: bool) -> crate::Result<TrashIndex> {
+        let dirs = match self.open_dirs(false) {
+            Ok(x) => x,
+            // The trash doesn't exist yet, there is nothing to index
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Ok(TrashIndex {
+                    trash: self,
+                    entries: Vec::new(),
+                })
+            }
+            Err(e) => return Err(e),
+        };
+
+        let index_path = index_path(&self);
+        let current_stamp = Stamp::capture(&dirs)?;
+
+        let mut exists = true;
+        let content = dirfd::open_file_at(
+            dirs.dir(),
+            Path::new(INDEX_FILENAME),
+            libc::O_RDONLY,
+            FILE_MODE,
+        )
+        .and_then(|mut file| {
+            let mut content = Vec::new();
+            file.read_to_end(&mut content).map(|_| content)
+        });
+        let (mut entries, stamp, records) = match content {
+            Ok(content) => replay(&content, &self),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                exists = false;
+                Some((HashMap::new(), None, 0))
+            }
+            // A symlink is never followed, it is replaced by the rewritten index
+            Err(e) if e.raw_os_error() == Some(libc::ELOOP) => None,
+            Err(e) => return Err(e).fs_err(FsOp::Read, &index_path),
+        }
+        .unwrap_or_else(|| {
+            event!(
+                warn,
+                "Discarding invalid index",
+                index = index_path.display()
+            );
+            (HashMap::new(), None, 0)
+        });
+
+        let outdated = stamp != Some(current_stamp);
+        if outdated {
+            event!(
+                debug,
+                "Index is outdated, reconciling",
+                index = index_path.display()
+            );
+            reconcile(&self, &mut entries)?;
+        }
+
+        // Rewrite the log once it contains mostly records that no longer matter
+        let bloated = records > 2 * entries.len() + 64;
+        if (outdated || bloated) && (exists || create) {
+            // The stamp from before reconciling is stored, so if the trash changed
+            // in the meantime, the next load will simply reconcile again.
+            if let Err(e) = write_compacted(&dirs, &entries, current_stamp) {
+                event!(
+                    warn,
+                    "Failed to write index",
+                    index = index_path.display(),
+                    error = e
+                );
+            }
+        }
+
+        drop(dirs);
+        Ok(TrashIndex {
+            trash: self,
+            entries: entries.into_values().collect(),
+        })
+    }
+}
+
+/// A change made to a trash by this crate
+pub(crate) enum Change<'a> {
+    Added(&'a TrashFile),
+    Removed(&'a OsStr),
+}
+
+/// Records changes made by this crate into an existing index.
+///
+/// Must be started *before* the trash is modified and committed right *after* it,
+/// both while holding the trash lock. Any change by another program in between
+/// can't be told apart from our own and would be missed by the index.
+pub(crate) struct Journal {
+    index_path: PathBuf,
+    before: Stamp,
+}
+
+impl Journal {
+    /// Returns `None` if the trash has no index
+    pub(crate) fn begin(dirs: &TrashDirs) -> Option<Self> {
+        let index_path = index_path(dirs.trash());
+        let stat = dirfd::stat_at(dirs.dir(), Path::new(INDEX_FILENAME)).ok()?;
+        if stat.st_mode & libc::S_IFMT != libc::S_IFREG {
+            return None;
+        }
+
+        Some(Self {
+            index_path,
+            before: Stamp::capture(dirs).ok()?,
+        })
+    }
+
+    /// Appends the changes to the index.
+    ///
+    /// The state of the trash is captured before anything else, so it matches the state
+    /// right after our own modification as closely as possible.
+    ///
+    /// Failures are only logged, as an index that didn't receive the changes
+    /// will be reconciled on the next load anyway.
+    pub(crate) fn commit(self, dirs: &TrashDirs, changes: &[Change]) {
+        let result = Stamp::capture(dirs).and_then(|after| self.commit_inner(dirs, after, changes));
+        if let Err(e) = result {
+            event!(
+                warn,
+                "Failed to update index",
+                index = self.index_path.display(),
+                error = e
+            );
+        }
+    }
+
+    fn commit_inner(
+        &self,
+        dirs: &TrashDirs,
+        after: Stamp,
+        changes: &[Change],
+    ) -> crate::Result<()> {
+        let mut record = String::new();
+        for change in changes {
+            match change {
+                Change::Added(file) => {
+                    let mut info_name = file.raw_filename().to_owned();
+                    info_name.push(".trashinfo");
+                    let info_meta = dirfd::stat_at(dirs.info(), Path::new(&info_name))
+                        .fs_err(FsOp::Stat, file.info_filepath())?;
+                    let mtime = (info_meta.st_mtime, info_meta.st_mtime_nsec);
+                    record.push_str(&IndexEntry::from_trash_file(file, mtime).serialize());
+                }
+                Change::Removed(name) => {
+                    record.push_str(&format!("-\t{}\n", encode(name.as_bytes())));
+                }
+            }
+        }
+        record.push_str(&format!("D\t{}\t{}\n", self.before, after));
+
+        // A single write on a file opened for appending, so that records of concurrent writers don't interleave
+        dirfd::open_file_at(
+            dirs.dir(),
+            Path::new(INDEX_FILENAME),
+            libc::O_WRONLY | libc::O_APPEND,
+            FILE_MODE,
+        )
+        .fs_err(FsOp::Open, &self.index_path)?
+        .write_all(record.as_bytes())
+        .fs_err(FsOp::Write, &self.index_path)?;
+        Ok(())
+    }
+}
+
+/// The modification times of the info and files directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp([i64; 4]);
+
+impl Stamp {
+    fn capture(dirs: &TrashDirs) -> crate::Result<Self> {
+        let trash = dirs.trash();
+        let info =
+            dirfd::stat_at(dirs.info(), Path::new(".")).fs_err(FsOp::Stat, trash.info_dir())?;
+        let files =
+            dirfd::stat_at(dirs.files(), Path::new(".")).fs_err(FsOp::Stat, trash.files_dir())?;
+        Ok(Self([
+            info.st_mtime,
+            info.st_mtime_nsec,
+            files.st_mtime,
+            files.st_mtime_nsec,
+        ]))
+    }
+}
+
+impl std::fmt::Display for Stamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [a, b, c, d] = self.0;
+        write!(f, "{a}:{b}:{c}:{d}")
+    }
+}
+
+impl FromStr for Stamp {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s
+            .split(':')
+            .map(|x| x.parse().map_err(|_| ()))
+            .collect::<Result<Vec<i64>, _>>()?;
+        Ok(Self(parts.try_into().map_err(|_| ())?))
+    }
+}
+
+fn index_path(trash: &Trash) -> PathBuf {
+    trash.trash_dir().join(INDEX_FILENAME)
+}
+
+/// Replays the log, returning `None` if the index is invalid.
+///
+/// Also returns the amount of records in the log.
+fn replay(
+    content: &[u8],
+    trash: &Rc<Trash>,
+) -> Option<(HashMap<OsString, IndexEntry>, Option<Stamp>, usize)> {
+    let content = std::str::from_utf8(content).ok()?;
+    let mut lines = content.lines();
+
+    let (header, mount_root) = lines.next()?.split_once('\t')?;
+    if header != INDEX_HEADER || Path::new(&decode(mount_root)) != trash.mount_root() {
+        // A different mount root changes the original path and thus the ID of every entry
+        return None;
+    }
+
+    let mut entries = HashMap::new();
+    let mut stamp = None;
+    let mut records = 0;
+    for line in lines {
+        records += 1;
+        let fields = line.split('\t').collect::<Vec<_>>();
+        match fields.as_slice() {
+            ["+", rest @ ..] => {
+                let entry = IndexEntry::parse(rest, trash)?;
+                entries.insert(entry.raw_filename.clone(), entry);
+            }
+            ["-", name] => {
+                entries.remove(&decode(name));
+            }
+            ["C"] => entries.clear(),
+            ["S", new] => stamp = Some(new.parse().ok()?),
+            ["D", before, after] => {
+                let before = before.parse::<Stamp>().ok()?;
+                stamp = if stamp == Some(before) {
+                    Some(after.parse().ok()?)
+                } else {
+                    None
+                };
+            }
+            // A partially written line from an interrupted process, the stamp won't match anyway.
+            _ => stamp = None,
+        }
+    }
+
+    Some((entries, stamp, records))
+}
+
+/// Brings the entries up to date with the actual contents of the trash,
+/// only parsing trashinfo files that are new or were modified.
+fn reconcile(trash: &Rc<Trash>, entries: &mut HashMap<OsString, IndexEntry>) -> crate::Result<()> {
+    let payloads: HashSet<OsString> = fs::read_dir(trash.files_dir())
+        .and_then(|x| x.map(|x| x.map(|x| x.file_name())).collect())
+        .fs_err(FsOp::ReadDir, trash.files_dir())?;
+
+    let mut seen = HashSet::new();
+    let info_files = fs::read_dir(trash.info_dir()).fs_err(FsOp::ReadDir, trash.info_dir())?;
+    for info_file in info_files {
+        let info_file = info_file.fs_err(FsOp::ReadDir, trash.info_dir())?;
+        let info_path = info_file.path();
+        if info_path.extension() != Some(OsStr::new("trashinfo")) {
+            continue;
+        }
+        let Some(name) = info_path.file_stem().map(ToOwned::to_owned) else {
+            continue;
+        };
+        if !payloads.contains(&name) {
+            continue;
+        }
+
+        let meta = info_file.metadata().fs_err(FsOp::Stat, &info_path)?;
+        let mtime = (meta.mtime(), meta.mtime_nsec());
+        let up_to_date = entries.get(&name).is_some_and(|x| x.info_mtime == mtime);
+        if !up_to_date {
+            match TrashFile::from_trashinfo_path(&info_path, trash.clone()) {
+                Ok(file) => {
+                    entries.insert(name.clone(), IndexEntry::from_trash_file(&file, mtime));
+                }
+                Err(e) => {
+                    event!(
+                        debug,
+                        "Not indexing",
+                        info_path = info_path.display(),
+                        error = e
+                    );
+                    continue;
+                }
+            }
+        }
+        seen.insert(name);
+    }
+
+    entries.retain(|name, _| seen.contains(name));
+    Ok(())
+}
+
+/// Atomically replaces the index with one only containing the given entries
+fn write_compacted(
+    dirs: &TrashDirs,
+    entries: &HashMap<OsString, IndexEntry>,
+    stamp: Stamp,
+) -> crate::Result<()> {
+    let trash = dirs.trash();
+    let mut content = format!(
+        "{INDEX_HEADER}\t{}\n",
+        encode(trash.mount_root().as_os_str().as_bytes())
+    );
+    for entry in entries.values() {
+        content.push_str(&entry.serialize());
+    }
+    content.push_str(&format!("S\t{stamp}\n"));
+
+    let tmp_path = trash.trash_dir().join(INDEX_TMP_FILENAME);
+    let index_path = index_path(trash);
+
+    // A leftover of an interrupted write is replaced, never followed if it is a symlink
+    match dirfd::unlink_at(dirs.dir(), Path::new(INDEX_TMP_FILENAME)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(e).fs_err(FsOp::RemoveFile, &tmp_path)
+        }
+        _ => {}
+    }
+    let mut tmp = dirfd::open_file_at(
+        dirs.dir(),
+        Path::new(INDEX_TMP_FILENAME),
+        libc::O_CREAT | libc::O_EXCL | libc::O_WRONLY,
+        FILE_MODE,
+    )
+    .fs_err(FsOp::Create, &tmp_path)?;
+    if let Some((uid, gid)) = trash.foreign_owner() {
+        fchown(&tmp, Some(uid), gid).fs_err(FsOp::Chown, &tmp_path)?;
+    }
+    tmp.write_all(content.as_bytes())
+        .fs_err(FsOp::Write, &tmp_path)?;
+    dirfd::rename_at(
+        dirs.dir(),
+        Path::new(INDEX_TMP_FILENAME),
+        dirs.dir(),
+        Path::new(INDEX_FILENAME),
+    )
+    .rename_err(&tmp_path, &index_path)?;
+    Ok(())
+}
+
+#[cfg(feature = "fs_extra")]
+fn calc_size(path: &Path) -> Option<u64> {
+    fs_extra::dir::get_size(path).ok()
+}
+
+#[cfg(not(feature = "fs_extra"))]
+fn calc_size(_path: &Path) -> Option<u64> {
+    None
+}
+
+fn encode(bytes: &[u8]) -> String {
+    urlencoding::encode_binary(bytes).into_owned()
+}
+
+fn decode(s: &str) -> OsString {
+    OsStr::from_bytes(&urlencoding::decode_binary(s.as_bytes())).to_owned()
+}
@@ -14,6 +14,8 @@
 //! - Recover trashed files
 //! - Empty trash
 //! - "Streaming" using iterators (for trashcans and trashed files)
+//! - An optional on-disk index for fast lookups and sorting in large trashcans (see [`Trash::index`])
+//! - Progress reporting and cancellation for long running operations (see [`ProgressHandler`])
 //!
 //! ## Linux only
 //! This crate is linux only for now, as it relies on reading `/proc/mounts` and uses some unix-only io extensions.
@@ -31,6 +33,8 @@
 //! | Feature | Description |
 //! | --- | --- |
 //! | `fs_extra` | adds `.size()` methods to [`TrashFile`] |
+//! | `tracing` | emits structured `tracing` events and wraps operations in spans, instead of using `log` |
+//! | `serde` | implements `Serialize` for [`Trash`], [`TrashFile`], [`TrashInfo`] and [`TrashType`], and `Deserialize` for [`TrashInfo`] and [`TrashType`] |
 //!
 //! # Example
 //! This example shows how to trash a file and list all trashed files
@@ -61,31 +65,69 @@
 //! | remove | Permanently remove a file from the trash |
 //! | empty | Permanently removes all trashes files |
 
+use discovery::examine_mount;
+use mounts::list_mounts;
+use progress::Tracker;
 use std::{
-    ffi::OsStr,
+    collections::HashSet,
     fmt::Debug,
     fs,
-    os::unix::{ffi::OsStrExt, fs::MetadataExt},
-    path::{Component, Path, PathBuf},
+    ops::ControlFlow,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
 #[cfg(test)]
 mod test;
 
+#[macro_use]
+mod macros;
+
+pub use discovery::{
+    discover, Candidate, CandidateStatus, DiscoveryReport, HomeResolution, MountReport,
+    RejectReason,
+};
 pub use error::*;
-pub use trash::{Trash, TrashType};
-pub use trash_file::TrashFile;
+pub use index::{IndexEntry, TrashIndex};
+pub use mounts::MountWatcher;
+pub use progress::{Progress, ProgressHandler};
+pub use trash::{EmptyReport, PermissionIssue, Trash, TrashTotals, TrashType};
+pub use trash_entry::{RawEntry, TrashEntry};
+pub use trash_file::{TrashFile, ID_LEN};
+pub use trashinfo::{TrashInfo, TrashInfoBuilder};
+pub use users::User;
 
+mod dirfd;
+mod discovery;
 mod error;
+mod index;
+mod mounts;
+mod progress;
+mod rename;
+#[cfg(feature = "serde")]
+mod serialize;
 mod trash;
+mod trash_entry;
 mod trash_file;
 mod trashinfo;
+mod users;
 
 /// Unifies all trashcans on the system into one interface.
 #[derive(Debug)]
 pub struct UnifiedTrash {
     known_trashes: Vec<Rc<Trash>>,
+    /// The user whose trashes are found or created when no known trash matches
+    uid: u32,
+}
+
+/// The changes made to the known trashcans by [`UnifiedTrash::refresh`]
+#[derive(Debug, Default)]
+pub struct RefreshSummary {
+    /// Trashcans on newly mounted filesystems
+    pub added: Vec<Rc<Trash>>,
+    /// Trashcans whose filesystem is no longer mounted
+    pub removed: Vec<Rc<Trash>>,
 }
 
 impl UnifiedTrash {
@@ -96,6 +138,58 @@ impl UnifiedTrash {
         Ok(Self::with_trashcans(trashes))
     }
 
+    /// Creates a unified trash with all trashcans of `user`, like [`Self::new`] does for the current user.
+    ///
+    /// Files are put into the trashes of `user`, which are created on demand like [`Self::put`] does.
+    /// When running as root, created directories and `.trashinfo` files are handed over to `user`,
+    /// so that they can see and restore the files. This is meant for programs run through
+    /// `sudo` or `pkexec`, see [`User::invoking`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// use xdg_trash::{UnifiedTrash, User};
+    ///
+    /// let mut trash = match User::invoking().unwrap() {
+    ///     Some(user) => UnifiedTrash::as_user(&user).unwrap(),
+    ///     None => UnifiedTrash::new().unwrap(),
+    /// };
+    /// trash.put("somefile.txt").unwrap();
+    /// ```
+    pub fn as_user(user: &User) -> crate::Result<Self> {
+        let _span = span!("as_user", user = user.name);
+        let home_trash = Trash::find_home_trash_of(user)
+            .map_err(|e| crate::Error::FailedToFindHomeTrash(Box::new(e)))?;
+        let mounts_iter = list_mounts()?
+            .into_iter()
+            .flat_map(|x| find_trashes_at(x, user.uid));
+
+        let mut unified =
+            Self::with_trashcans([Rc::new(home_trash)].into_iter().chain(mounts_iter));
+        unified.uid = user.uid;
+        Ok(unified)
+    }
+
+    /// Creates a unified trash with the trashcans of the given users, see [`list_trashes_of`].
+    ///
+    /// This is meant for administrators managing the trashes of other users, which requires root.
+    /// Entries can be attributed to their user through [`Trash::owner`] of their trash.
+    /// Restoring a file as root keeps it's owner and hands any recreated parent directories
+    /// over to the user of the trash.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use xdg_trash::{UnifiedTrash, User};
+    ///
+    /// let trash = UnifiedTrash::for_users(&User::all().unwrap()).unwrap();
+    /// for file in trash.list().filter_map(Result::ok) {
+    ///     println!("{}: {}", file.trash().owner(), file.original_path().display());
+    /// }
+    /// ```
+    pub fn for_users(users: &[User]) -> crate::Result<Self> {
+        let trashes = list_trashes_of(users)?;
+        Ok(Self::with_trashcans(trashes.into_iter()))
+    }
+
     /// Creates a new unified trash with a custom selection of trashcans.
     /// Should only be used if you know what you're doing (you've read the xdg-trash spec).
     ///
@@ -121,6 +215,7 @@ impl UnifiedTrash {
         sort_trashes(&mut trashes);
         Self {
             known_trashes: trashes,
+            uid: unsafe { libc::getuid() },
         }
     }
 
@@ -135,101 +230,428 @@ impl UnifiedTrash {
             .flatten()
     }
 
+    /// Returns an iterator over all entries in all *known* trashcans, without reading any of them.
+    ///
+    /// Use this instead of [`Self::list`] if only the names or the amount of trashed files are needed.
+    pub fn entries(&self) -> impl Iterator<Item = crate::Result<TrashEntry>> + '_ {
+        self.known_trashes
+            .iter()
+            .flat_map(|trash| trash.clone().entries())
+            .flatten()
+    }
+
+    /// Returns an iterator over every entry in all *known* trashcans, including broken ones.
+    ///
+    /// See [`Trash::list_raw`].
+    pub fn list_raw(&self) -> impl Iterator<Item = crate::Result<RawEntry>> + '_ {
+        self.known_trashes
+            .iter()
+            .flat_map(|trash| trash.clone().list_raw())
+            .flatten()
+    }
+
+    /// Returns the on-disk index (see [`Trash::index`]) of every *known* trashcan,
+    /// creating it where it doesn't exist yet.
+    ///
+    /// This is much faster than [`Self::list`] for large trashcans, if all entries
+    /// are needed at once (for example to look up or sort files).
+    pub fn indexes(&self) -> impl Iterator<Item = crate::Result<TrashIndex>> + '_ {
+        self.known_trashes.iter().map(|trash| trash.clone().index())
+    }
+
+    /// Like [`Self::indexes`], but never creates a new index on disk (see [`Trash::existing_index`]).
+    pub fn existing_indexes(&self) -> impl Iterator<Item = crate::Result<TrashIndex>> + '_ {
+        self.known_trashes
+            .iter()
+            .map(|trash| trash.clone().existing_index())
+    }
+
     /// Puts the file at `input_path` into one of the *known* trashcans, failing if no matching trashcan is known.
     ///
+    /// Files on the device of a known home trash that no other writable trashcan matches go into the home trash.
+    ///
     /// Returns the created trashfile.
     pub fn put_known(&mut self, input_path: impl AsRef<Path>) -> crate::Result<TrashFile> {
-        self.put_inner(input_path.as_ref(), true)
+        self.put_inner(input_path.as_ref(), true, &[])
     }
 
     /// Puts the file at `input_path` into a trashcan, creating a new one if one doesn't exist.
     ///
+    /// If no known trashcan matches, a writable trash in the top directory of the files filesystem is used.
+    /// Without one, files on the device of the home trash go into the home trash, and a new
+    /// `.Trash-{uid}` is only created for files on other devices.
+    ///
     /// Returns the created trashfile.
     pub fn put(&mut self, input_path: impl AsRef<Path>) -> crate::Result<TrashFile> {
-        self.put_inner(input_path.as_ref(), false)
+        self.put_inner(input_path.as_ref(), false, &[])
+    }
+
+    /// Like [`Self::put`], but also records the extension keys in `extra`, see [`Trash::put_with_extra`].
+    ///
+    /// # Example
+    /// ```
+    /// use xdg_trash::UnifiedTrash;
+    /// use std::fs::File;
+    ///
+    /// let mut trash = UnifiedTrash::new().unwrap();
+    /// _ = File::create("ticket.txt").unwrap();
+    ///
+    /// let file = trash
+    ///     .put_with_extra("ticket.txt", &[("X-Ticket", "OPS-1234")])
+    ///     .unwrap();
+    /// assert_eq!(file.get_extra("X-Ticket"), Some("OPS-1234"));
+    /// ```
+    pub fn put_with_extra(
+        &mut self,
+        input_path: impl AsRef<Path>,
+        extra: &[(&str, &str)],
+    ) -> crate::Result<TrashFile> {
+        self.put_inner(input_path.as_ref(), false, extra)
     }
 
-    fn put_inner(&mut self, input_path: &Path, known_only: bool) -> crate::Result<TrashFile> {
-        let input_path_meta = fs::symlink_metadata(input_path).map_err(|e| {
-            crate::Error::FailedToTrashFile(
-                input_path.to_owned(),
-                Box::new(crate::Error::IoError(e)),
-            )
-        })?;
-        let input_abs = lexical_absolute(input_path)?;
+    /// Puts all files in `input_paths` into trashcans like [`UnifiedTrash::put`],
+    /// reporting the progress to `progress` after each file.
+    ///
+    /// The bytes reported are the sizes of the trashed files, failed files are counted
+    /// in [`Progress::items_failed`]. The handler can stop at the first failure by returning
+    /// [`ControlFlow::Break`] once that is non-zero.
+    ///
+    /// Returns a result for every input path, in the same order.
+    /// When cancelled, the remaining files are not trashed and their result is [`Error::Cancelled`].
+    pub fn put_all<P: AsRef<Path>>(
+        &mut self,
+        input_paths: &[P],
+        progress: &mut dyn ProgressHandler,
+    ) -> Vec<crate::Result<TrashFile>> {
+        let mut tracker = Tracker::start(progress, input_paths.len() as u64, None).ok();
+        input_paths
+            .iter()
+            .map(|input_path| {
+                let Some(active) = tracker.as_mut() else {
+                    return Err(crate::Error::Cancelled);
+                };
+                let result = self.put(input_path);
+                let reported = match &result {
+                    Ok(file) => {
+                        active.item_done(trash::apparent_size(&file.files_filepath()).unwrap_or(0))
+                    }
+                    Err(_) => active.item_failed(),
+                };
+                if reported.is_err() {
+                    tracker = None;
+                }
+                result
+            })
+            .collect()
+    }
+
+    fn put_inner(
+        &mut self,
+        input_path: &Path,
+        known_only: bool,
+        extra: &[(&str, &str)],
+    ) -> crate::Result<TrashFile> {
+        let span = span!("select_trash", input = input_path.display(); trash);
+        let input_path_meta = fs::symlink_metadata(input_path)
+            .fs_err(FsOp::Stat, input_path)
+            .map_err(|e| crate::Error::FailedToTrashFile(input_path.to_owned(), Box::new(e)))?;
+        let input_abs = physical_absolute(input_path)
+            .map_err(|e| crate::Error::FailedToTrashFile(input_path.to_owned(), Box::new(e)))?;
         let trash = if let Some(known_trash) = self
             .known_trashes
             .iter()
-            .inspect(|x| log::trace!("Checking: {:?}", x.mount_root()))
+            .inspect(|x| event!(trace, "Checking trash", trash = x.trash_dir().display()))
             .find(|trash| {
                 // Checks if file is on the same physical device as the trashcan
                 trash.device() == input_path_meta.dev()
                 // checks if the file is a child of the trash mount root, if this is not the case,
                 // it means that multiple trashes exist on the same device. In this case, we just continue searching
-                && input_abs.strip_prefix(trash.based_on()).is_ok()
+                && input_abs.strip_prefix(physical_or_given(trash.based_on())).is_ok()
+                // Trashes that can't be written to are known for listing, files go into the home trash instead
+                && trash.is_writable()
             }) {
-            log::trace!("Found matching trash");
+            event!(
+                trace,
+                "Found matching trash",
+                trash = known_trash.trash_dir().display()
+            );
             known_trash.clone()
-        } else if known_only {
-            return Err(crate::Error::NoTrashFound);
         } else {
-            log::trace!("No trash found, trying to find or create one");
+            // The spec allows the home trash for any file on it's device, not only those inside of $HOME
+            let home_trash = self
+                .known_trashes
+                .iter()
+                .find(|x| x.trash_type() == TrashType::Home && x.device() == input_path_meta.dev())
+                .cloned();
+            if known_only {
+                home_trash.ok_or(crate::Error::NoTrashFound)?
+            } else {
+                self.find_or_create_trash(input_path, home_trash)?
+            }
+        };
+        span.record("trash", trash.trash_dir().display());
+        drop(span);
 
-            let mount_root = find_mount_root(input_path)?;
+        trash.put_with_extra(input_path, extra)
+    }
 
-            let trash = if let Some(found_trash) = find_any_trash_at(mount_root.clone()) {
-                found_trash
-            } else {
-                Trash::create_user_trash(mount_root).map_err(|e| {
-                    crate::Error::FailedToCreateTrash(input_path.to_owned(), Box::new(e))
-                })?
-            };
+    /// Finds a trash for a file that no known trash matched.
+    ///
+    /// This is the first writable trash in the top directory of the files filesystem, or `home_trash`
+    /// if there is none. Only if neither exist, a new `.Trash-{uid}` is created.
+    fn find_or_create_trash(
+        &mut self,
+        input_path: &Path,
+        home_trash: Option<Rc<Trash>>,
+    ) -> crate::Result<Rc<Trash>> {
+        event!(trace, "No trash found, trying to find or create one");
+        let mount_root = find_mount_root(input_path)?;
 
-            let trash = Rc::new(trash);
+        // All trashes on the mount are remembered for listing, even if they can't be written to
+        let found = find_trashes_at(mount_root.clone(), self.uid);
+        let writable = found.iter().find(|x| x.is_writable()).cloned();
+        self.remember(found);
 
-            self.known_trashes.push(trash.clone());
-            sort_trashes(&mut self.known_trashes);
-            trash
-        };
+        if let Some(trash) = writable {
+            return Ok(trash);
+        }
+        if let Some(trash) = home_trash {
+            event!(
+                debug,
+                "No writable trash on the filesystem, using the home trash",
+                mount_root = mount_root.display()
+            );
+            return Ok(trash);
+        }
+
+        let trash = Rc::new(
+            Trash::create_user_trash_of(mount_root, self.uid).map_err(|e| {
+                crate::Error::FailedToCreateTrash(input_path.to_owned(), Box::new(e))
+            })?,
+        );
+        self.remember(vec![trash.clone()]);
+        Ok(trash)
+    }
+
+    /// Adds the trashes to the known ones, skipping those that are already known
+    fn remember(&mut self, trashes: Vec<Rc<Trash>>) {
+        for trash in trashes {
+            if self
+                .known_trashes
+                .iter()
+                .all(|x| x.info_dir() != trash.info_dir())
+            {
+                self.known_trashes.push(trash);
+            }
+        }
+        sort_trashes(&mut self.known_trashes);
+    }
+
+    /// Permanently removes all entries in the *known* trash cans, including broken ones.
+    ///
+    /// See [`Trash::empty`].
+    pub fn empty(&self) -> EmptyReport {
+        self.empty_with_progress(&mut |_: &Progress| ControlFlow::Continue(()))
+    }
+
+    /// Like [`UnifiedTrash::empty`], but reports the progress across all *known* trashcans
+    /// to `progress` after each entry.
+    pub fn empty_with_progress(&self, progress: &mut dyn ProgressHandler) -> EmptyReport {
+        trash::remove_raw_where(&self.known_trashes, &mut |_| true, progress)
+    }
 
-        log::trace!("Putting into trash");
-        trash.put(input_path)
+    /// Permanently removes all valid entries in the *known* trash cans for which `predicate` returns `true`.
+    ///
+    /// See [`Trash::remove_where`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// use chrono::{Days, Local};
+    /// use xdg_trash::UnifiedTrash;
+    ///
+    /// let trash = UnifiedTrash::new().unwrap();
+    /// let month_ago = Local::now().naive_local() - Days::new(30);
+    /// let report = trash.remove_where(|file| file.deleted_at() < month_ago);
+    /// println!("Freed {} bytes", report.bytes_freed);
+    /// ```
+    pub fn remove_where(&self, predicate: impl FnMut(&TrashFile) -> bool) -> EmptyReport {
+        self.remove_where_with_progress(predicate, &mut |_: &Progress| ControlFlow::Continue(()))
     }
 
-    /// Permanently removes all trashed files in the *known* trash cans.
-    pub fn empty(&self) -> crate::Result<impl Iterator<Item = crate::Result<PathBuf>> + '_> {
-        Ok(self
+    /// Like [`UnifiedTrash::remove_where`], but reports the progress across all *known* trashcans
+    /// to `progress` after each removed entry.
+    pub fn remove_where_with_progress(
+        &self,
+        mut predicate: impl FnMut(&TrashFile) -> bool,
+        progress: &mut dyn ProgressHandler,
+    ) -> EmptyReport {
+        trash::remove_raw_where(
+            &self.known_trashes,
+            &mut |x| matches!(x, RawEntry::Valid(file) if predicate(file)),
+            progress,
+        )
+    }
+
+    /// Calculates the size in bytes of all files in the *known* trashcans,
+    /// see [`Trash::size_with_progress`].
+    pub fn size_with_progress(&self, progress: &mut dyn ProgressHandler) -> crate::Result<u64> {
+        let mut payloads = Vec::new();
+        for trash in &self.known_trashes {
+            payloads.extend(trash.payloads()?);
+        }
+
+        trash::size_of_payloads(&payloads, progress)
+    }
+
+    /// Re-reads the mount table, adding trashcans on newly mounted filesystems and
+    /// dropping trashcans whose filesystem is gone.
+    ///
+    /// A trashcan counts as gone if it's mount root no longer resides on the device
+    /// it was found on (see [`Trash::is_available`]). The home trash is never dropped.
+    ///
+    /// [`TrashFile`]s of dropped trashcans that are still held keep their trash alive,
+    /// but operations on them will fail, as their files are no longer reachable.
+    ///
+    /// Use a [`MountWatcher`] to find out when this should be called.
+    pub fn refresh(&mut self) -> crate::Result<RefreshSummary> {
+        let _span = span!("refresh");
+        let mut summary = RefreshSummary::default();
+
+        let (kept, removed) = self
             .known_trashes
-            .iter()
-            .flat_map(|trash| trash.empty())
-            .flatten())
+            .drain(..)
+            .partition(|x| x.trash_type() == TrashType::Home || x.is_available());
+        self.known_trashes = kept;
+        summary.removed = removed;
+
+        for trash in list_mounts()?
+            .into_iter()
+            .flat_map(|x| find_trashes_at(x, self.uid))
+        {
+            if self
+                .known_trashes
+                .iter()
+                .all(|x| x.info_dir() != trash.info_dir())
+            {
+                event!(
+                    debug,
+                    "Found new trash",
+                    trash = trash.trash_dir().display()
+                );
+                self.known_trashes.push(trash.clone());
+                summary.added.push(trash);
+            }
+        }
+
+        sort_trashes(&mut self.known_trashes);
+        Ok(summary)
+    }
+
+    /// Calls [`Self::refresh`] if the watcher reports that the mount table changed.
+    pub fn refresh_if_changed(
+        &mut self,
+        watcher: &mut MountWatcher,
+    ) -> crate::Result<Option<RefreshSummary>> {
+        if watcher.changed()? {
+            self.refresh().map(Some)
+        } else {
+            Ok(None)
+        }
     }
 
     /// Removes broken trashinfo files from all *known* trashcans
     pub fn fix(&self) -> crate::Result<usize> {
-        self.known_trashes
-            .iter()
-            .map(|trash| trash.clone().fix())
-            .try_fold(0, |state, y| y.map(|x| state + x))
+        self.fix_with_progress(&mut |_: &Progress| ControlFlow::Continue(()))
+    }
+
+    /// Like [`UnifiedTrash::fix`], but reports the progress across all *known* trashcans
+    /// to `progress` after each checked trashinfo file.
+    pub fn fix_with_progress(&self, progress: &mut dyn ProgressHandler) -> crate::Result<usize> {
+        let _span = span!("fix", trashes = self.known_trashes.len());
+        let mut info_files = Vec::new();
+        for trash in &self.known_trashes {
+            trash.purge_expunged()?;
+            info_files.extend(trash.info_files()?.into_iter().map(|x| (trash, x)));
+        }
+
+        let mut tracker = Tracker::start(progress, info_files.len() as u64, None)?;
+        let mut total = 0;
+        for (trash, info_file) in &info_files {
+            if Rc::clone(trash).fix_info_file(info_file)? {
+                total += 1;
+            }
+            tracker.item_done(0)?;
+        }
+        Ok(total)
+    }
+
+    /// Checks if other users can access any of the *known* trashcans, see [`Trash::check_permissions`]
+    pub fn check_permissions(&self, fix: bool) -> crate::Result<Vec<PermissionIssue>> {
+        let mut issues = Vec::new();
+        for trash in &self.known_trashes {
+            issues.extend(trash.check_permissions(fix)?);
+        }
+        Ok(issues)
     }
 }
 
 /// Returns an iterator over all trashes (not trashed files) available on the system.
 ///
 /// The home trash is guaranteed to be the fist item yielded by this iterator.
+/// A filesystem can have both an admin and a user trash, in which case both are yielded,
+/// the admin trash first.
+///
+/// Discovery never writes to disk, so this also works on read-only filesystems.
+/// Trashes that only partially exist are created once something is put into them.
 pub fn list_trashes() -> crate::Result<impl Iterator<Item = Rc<Trash>>> {
-    let home_trash = Trash::find_or_create_home_trash()
-        .map_err(|e| crate::Error::FailedToFindHomeTrash(Box::new(e)))?;
-    let mounts_iter = list_mounts()?.into_iter().filter_map(find_any_trash_at);
+    let _span = span!("list_trashes");
+    let home_trash =
+        Trash::find_home_trash().map_err(|e| crate::Error::FailedToFindHomeTrash(Box::new(e)))?;
+    let uid = unsafe { libc::getuid() };
+    let mounts_iter = list_mounts()?
+        .into_iter()
+        .flat_map(move |x| find_trashes_at(x, uid));
 
-    Ok([home_trash].into_iter().chain(mounts_iter).map(Rc::new))
+    Ok([Rc::new(home_trash)].into_iter().chain(mounts_iter))
 }
 
-fn find_any_trash_at(mount_root: PathBuf) -> Option<Trash> {
-    let admin_trash = Trash::find_admin_trash(mount_root.clone());
-    let user_trash = Trash::find_user_trash(mount_root);
+/// Returns the trashes of all given users, like [`list_trashes`] does for the current user.
+///
+/// For every user, these are the home trash in their home directory from the passwd database
+/// (see [`Trash::find_home_trash_of`]), and their `.Trash/{uid}` and `.Trash-{uid}` directories
+/// on every mounted filesystem. Unlike [`list_trashes`], only trashes that already exist are returned.
+pub fn list_trashes_of(users: &[User]) -> crate::Result<Vec<Rc<Trash>>> {
+    let _span = span!("list_trashes_of", users = users.len());
+    let mounts = list_mounts()?;
+    let mut seen = HashSet::new();
+    let mut trashes = Vec::new();
 
-    admin_trash.ok().or(user_trash.ok())
+    for user in users {
+        let home_trash = match Trash::find_home_trash_of(user) {
+            Ok(x) => Some(Rc::new(x)),
+            Err(e) => {
+                event!(debug, "No home trash for user", user = user.name, error = e);
+                None
+            }
+        };
+        let mount_trashes = mounts
+            .iter()
+            .flat_map(|x| find_trashes_at(x.clone(), user.uid));
+
+        // Users sharing a home directory would otherwise list the same trash multiple times
+        for trash in home_trash.into_iter().chain(mount_trashes) {
+            if trash.trash_dir().is_dir() && seen.insert(trash.info_dir().to_owned()) {
+                trashes.push(trash);
+            }
+        }
+    }
+
+    Ok(trashes)
+}
+
+/// Finds all trashes of the user `uid` at the given mount root, ordered by their priority
+fn find_trashes_at(mount_root: PathBuf, uid: u32) -> Vec<Rc<Trash>> {
+    examine_mount(mount_root, uid).accepted().cloned().collect()
 }
 
 /// Sorts trashes by their priority such that admin trashes will always be before user trashes
@@ -237,49 +659,41 @@ fn sort_trashes(trashes: &mut [Rc<Trash>]) {
     trashes.sort_by_key(|x| -x.trash_type().priority());
 }
 
-/// Lists all mounted filesystems (on linux)
-#[cfg(target_os = "linux")]
-fn list_mounts() -> crate::Result<Vec<PathBuf>> {
-    fs::read("/proc/mounts")
-        .map_err(|_| crate::Error::InvalidProcMounts)?
-        .split(|x| *x == b'\n')
-        .filter(|x| !x.is_empty())
-        .map(|x| x.split(|x| *x == b' ').nth(1))
-        .map(|x| x.map(OsStr::from_bytes))
-        .map(|x| x.map(PathBuf::from).ok_or(crate::Error::InvalidProcMounts))
-        .collect()
+/// Makes `p` absolute, resolving symlinks and `..` in every component except the last one.
+///
+/// The parent directory is resolved physically, like the kernel does when accessing `p`. With the
+/// current directory being a symlink, `../x` therefore names the file next to the symlink target,
+/// not the one next to the symlink. The final component is kept as is, so a symlink is trashed
+/// instead of it's target.
+fn physical_absolute(p: &Path) -> crate::Result<PathBuf> {
+    let Some(name) = p.file_name() else {
+        // Paths ending in `..` or the root, which can't be symlinks
+        return fs::canonicalize(p).fs_err(FsOp::Resolve, p);
+    };
+    let parent = match p.parent() {
+        Some(x) if !x.as_os_str().is_empty() => x,
+        _ => Path::new("."),
+    };
+    Ok(fs::canonicalize(parent)
+        .fs_err(FsOp::Resolve, parent)?
+        .join(name))
 }
 
-/// like [`fs::canonicalize`] but doesn't follow symlinks and doesn't check if the file exists.
+/// The physical location of `path` (see [`physical_absolute`]), or `path` itself if it can't be resolved.
 ///
-/// Credit: <https://internals.rust-lang.org/t/path-to-lexical-absolute/14940>
-fn lexical_absolute(p: &Path) -> std::io::Result<PathBuf> {
-    let mut absolute = if p.is_absolute() {
-        PathBuf::new()
-    } else {
-        std::env::current_dir()?
-    };
-    for component in p.components() {
-        match component {
-            Component::CurDir => {}
-            Component::ParentDir => {
-                absolute.pop();
-            }
-            component => absolute.push(component.as_os_str()),
-        }
-    }
-    Ok(absolute)
+/// Used for the locations of trashes, so they are compared against physical paths.
+fn physical_or_given(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
 }
 
 /// Finds the mount point of the filesystem on which the path resides
 fn find_mount_root(path: &Path) -> crate::Result<PathBuf> {
-    let path = lexical_absolute(path)?;
-    let root_dev = fs::symlink_metadata(&path)?.dev();
+    let path = physical_absolute(path)?;
+    let root_dev = fs::symlink_metadata(&path).fs_err(FsOp::Stat, &path)?.dev();
     path.ancestors()
         .map(|p| (p, fs::metadata(p)))
         .map(|(p, x)| (p, x.map(|x| x.dev())))
         .take_while(|(_, x)| x.as_ref().ok() == Some(&root_dev))
-        .map(|(p, x)| x.map(|_| p))
-        .map(|x| x.map_err(crate::Error::IoError))
+        .map(|(p, x)| x.map(|_| p).fs_err(FsOp::Stat, p))
         .collect()
 }
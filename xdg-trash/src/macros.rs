@@ -0,0 +1,74 @@
+//! Logging that works with both `log` and, with the `tracing` feature, `tracing`.
+//!
+//! Events are written as `event!(level, "message", key = value, ...)`, all values have to
+//! implement `Display`. With `tracing`, the values become fields of the event, otherwise
+//! they are appended to the message as `key=value`.
+//!
+//! Operations are wrapped in spans with `span!("name", key = value, ...; empty, ...)`,
+//! the fields after the `;` are filled in later through [`OpSpan::record`].
+//! Spans of lazy iterators are exited with [`OpSpan::exit`] and re-entered for every item.
+//! Without `tracing`, spans do nothing.
+
+macro_rules! event {
+    ($level:ident, $msg:literal $(, $key:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($key = %$value,)* $msg);
+        #[cfg(not(feature = "tracing"))]
+        log::$level!(concat!($msg $(, " ", stringify!($key), "={}")*) $(, $value)*);
+    }};
+}
+
+macro_rules! span {
+    ($name:literal $(, $key:ident = $value:expr)* $(; $($empty:ident),+)? $(,)?) => {
+        $crate::macros::OpSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                $name,
+                $($key = %$value,)*
+                $($($empty = tracing::field::Empty,)+)?
+            )
+            .entered(),
+        }
+    };
+}
+
+/// An entered span, exited once dropped
+#[must_use]
+pub(crate) struct OpSpan {
+    #[cfg(feature = "tracing")]
+    pub(crate) span: tracing::span::EnteredSpan,
+}
+
+impl OpSpan {
+    /// Fills in a field that was declared empty in [`span!`]
+    #[allow(unused_variables)]
+    pub(crate) fn record(&self, field: &'static str, value: impl std::fmt::Display) {
+        #[cfg(feature = "tracing")]
+        self.span.record(field, tracing::field::display(value));
+    }
+
+    /// Exits the span, so it can be moved into a lazy iterator and entered for every item
+    pub(crate) fn exit(self) -> LazySpan {
+        LazySpan {
+            #[cfg(feature = "tracing")]
+            span: self.span.exit(),
+        }
+    }
+}
+
+/// A span that is only entered while running [`LazySpan::in_scope`]
+#[derive(Clone)]
+pub(crate) struct LazySpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl LazySpan {
+    /// Runs `f` inside of the span
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+}
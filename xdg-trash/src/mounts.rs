@@ -0,0 +1,122 @@
+use crate::error::{FsOp, IoResultExt};
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    os::{
+        fd::{AsRawFd, RawFd},
+        unix::ffi::OsStringExt,
+    },
+    path::PathBuf,
+    time::Duration,
+};
+
+const MOUNTINFO: &str = "/proc/self/mountinfo";
+
+/// Lists all mounted filesystems (on linux)
+#[cfg(target_os = "linux")]
+pub(crate) fn list_mounts() -> crate::Result<Vec<PathBuf>> {
+    fs::read("/proc/mounts")
+        .map_err(|_| crate::Error::InvalidProcMounts)?
+        .split(|x| *x == b'\n')
+        .filter(|x| !x.is_empty())
+        .map(|x| x.split(|x| *x == b' ').nth(1))
+        .map(|x| x.map(unescape))
+        .map(|x| x.ok_or(crate::Error::InvalidProcMounts))
+        .collect()
+}
+
+/// Undoes the octal escaping of whitespace and backslashes (`\040` for a space) done by the kernel
+fn unescape(field: &[u8]) -> PathBuf {
+    let mut out = Vec::with_capacity(field.len());
+    let mut i = 0;
+    while i < field.len() {
+        let escaped = field
+            .get(i + 1..i + 4)
+            .filter(|_| field[i] == b'\\')
+            .and_then(|x| std::str::from_utf8(x).ok())
+            .and_then(|x| u8::from_str_radix(x, 8).ok());
+
+        if let Some(byte) = escaped {
+            out.push(byte);
+            i += 4;
+        } else {
+            out.push(field[i]);
+            i += 1;
+        }
+    }
+    PathBuf::from(OsString::from_vec(out))
+}
+
+/// Watches the mount table of the current process for changes.
+///
+/// The kernel marks `/proc/self/mountinfo` as changed whenever a filesystem is mounted or unmounted,
+/// this is used to find out when [`crate::UnifiedTrash::refresh`] should be called.
+/// The watcher can also be integrated into an existing event loop through it's file descriptor,
+/// which signals `POLLPRI` on changes.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use xdg_trash::{MountWatcher, UnifiedTrash};
+///
+/// let mut trash = UnifiedTrash::new().unwrap();
+/// let mut watcher = MountWatcher::new().unwrap();
+///
+/// loop {
+///     if watcher.wait(Some(Duration::from_secs(5))).unwrap() {
+///         let summary = trash.refresh().unwrap();
+///         println!("{} trashes added, {} removed", summary.added.len(), summary.removed.len());
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MountWatcher {
+    mountinfo: File,
+}
+
+impl MountWatcher {
+    /// Starts watching the mount table. Only changes after this call are reported.
+    pub fn new() -> crate::Result<Self> {
+        let mountinfo = File::open(MOUNTINFO).fs_err(FsOp::Open, MOUNTINFO)?;
+        let mut watcher = Self { mountinfo };
+        // The kernel only raises `POLLPRI` once the table changed since it was opened,
+        // this consumes a change that raced with opening, so only later changes are reported
+        watcher.wait(Some(Duration::ZERO))?;
+        Ok(watcher)
+    }
+
+    /// Returns `true` if the mount table changed since the last call, without blocking
+    pub fn changed(&mut self) -> crate::Result<bool> {
+        self.wait(Some(Duration::ZERO))
+    }
+
+    /// Blocks until the mount table changes or the timeout elapses (forever if `None`).
+    ///
+    /// Returns `true` if the mount table changed since the last call.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> crate::Result<bool> {
+        let mut pollfd = libc::pollfd {
+            fd: self.mountinfo.as_raw_fd(),
+            events: libc::POLLPRI,
+            revents: 0,
+        };
+        let timeout = timeout.map_or(-1, |x| x.as_millis().try_into().unwrap_or(libc::c_int::MAX));
+
+        loop {
+            let res = unsafe { libc::poll(&mut pollfd, 1, timeout) };
+            if res >= 0 {
+                return Ok(pollfd.revents & (libc::POLLPRI | libc::POLLERR) != 0);
+            }
+
+            let e = std::io::Error::last_os_error();
+            if e.kind() != std::io::ErrorKind::Interrupted {
+                return Err(e).fs_err(FsOp::Read, MOUNTINFO);
+            }
+        }
+    }
+}
+
+impl AsRawFd for MountWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.mountinfo.as_raw_fd()
+    }
+}
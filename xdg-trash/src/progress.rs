@@ -0,0 +1,106 @@
+use std::ops::ControlFlow;
+
+/// The progress of a long running operation, passed to a [`ProgressHandler`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Items that were processed so far
+    pub items_done: u64,
+    /// Items that will be processed in total
+    pub items_total: u64,
+    /// Bytes that were processed so far
+    pub bytes_done: u64,
+    /// Bytes that will be processed in total, if known up front
+    pub bytes_total: Option<u64>,
+    /// Items that failed so far, also counted in `items_done`.
+    ///
+    /// Only operations that continue past failed items, like [`crate::UnifiedTrash::put_all`],
+    /// report these. Return [`ControlFlow::Break`] to stop at the first failure.
+    pub items_failed: u64,
+}
+
+/// Receives progress updates from long running operations and decides whether they continue.
+///
+/// The handler is called once before the first item and after every processed item.
+/// Returning [`ControlFlow::Break`] cancels the operation before the next item,
+/// which then fails with [`crate::Error::Cancelled`]. Items are never cancelled halfway,
+/// so the trash stays consistent.
+///
+/// This is implemented for all closures taking a [`Progress`].
+///
+/// # Example
+/// ```no_run
+/// use std::ops::ControlFlow;
+/// use xdg_trash::UnifiedTrash;
+///
+/// let trash = UnifiedTrash::new().unwrap();
+/// let removed = trash
+///     .fix_with_progress(&mut |p: &xdg_trash::Progress| {
+///         println!("{}/{}", p.items_done, p.items_total);
+///         ControlFlow::Continue(())
+///     })
+///     .unwrap();
+/// ```
+pub trait ProgressHandler {
+    /// Called with the current progress, return [`ControlFlow::Break`] to cancel
+    fn update(&mut self, progress: &Progress) -> ControlFlow<()>;
+}
+
+impl<F> ProgressHandler for F
+where
+    F: FnMut(&Progress) -> ControlFlow<()>,
+{
+    fn update(&mut self, progress: &Progress) -> ControlFlow<()> {
+        self(progress)
+    }
+}
+
+/// Keeps track of the progress of an operation and reports it to a [`ProgressHandler`]
+pub(crate) struct Tracker<'a> {
+    progress: Progress,
+    handler: &'a mut dyn ProgressHandler,
+}
+
+impl<'a> Tracker<'a> {
+    /// Reports the initial progress, failing if the handler cancels right away
+    pub(crate) fn start(
+        handler: &'a mut dyn ProgressHandler,
+        items_total: u64,
+        bytes_total: Option<u64>,
+    ) -> crate::Result<Self> {
+        let mut tracker = Self {
+            progress: Progress {
+                items_total,
+                bytes_total,
+                ..Default::default()
+            },
+            handler,
+        };
+        tracker.report()?;
+        Ok(tracker)
+    }
+
+    /// Marks an item as done, failing if the handler cancels
+    pub(crate) fn item_done(&mut self, bytes: u64) -> crate::Result<()> {
+        self.progress.items_done += 1;
+        self.progress.bytes_done += bytes;
+        self.report()
+    }
+
+    /// Marks an item as failed, failing if the handler cancels
+    pub(crate) fn item_failed(&mut self) -> crate::Result<()> {
+        self.progress.items_done += 1;
+        self.progress.items_failed += 1;
+        self.report()
+    }
+
+    pub(crate) fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    fn report(&mut self) -> crate::Result<()> {
+        match self.handler.update(&self.progress) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => Err(crate::Error::Cancelled),
+        }
+    }
+}
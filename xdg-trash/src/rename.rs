@@ -0,0 +1,62 @@
+use crate::dirfd::{is_dir, link_at, rename_at, renameat2_noreplace, stat_at, unlink_at, Dir};
+use std::{
+    io,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Set once the kernel reported that it doesn't know `renameat2` at all
+static NO_RENAMEAT2: AtomicBool = AtomicBool::new(false);
+
+/// Moves `from` to `to`, failing with [`io::ErrorKind::AlreadyExists`] if `to` exists.
+///
+/// Uses `renameat2(RENAME_NOREPLACE)`, which does the check and the rename atomically.
+/// Some filesystems (and old kernels) don't support it. For files, a hard link followed by
+/// removing `from` is used instead, which is just as atomic. Directories can't be hard linked,
+/// so they fall back to checking for `to` before a regular rename.
+pub(crate) fn rename_noreplace(
+    from_dir: Dir,
+    from: &Path,
+    to_dir: Dir,
+    to: &Path,
+) -> io::Result<()> {
+    if !NO_RENAMEAT2.load(Ordering::Relaxed) {
+        match renameat2_noreplace(from_dir, from, to_dir, to) {
+            Err(e) if e.raw_os_error() == Some(libc::ENOSYS) => {
+                NO_RENAMEAT2.store(true, Ordering::Relaxed);
+            }
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {}
+            other => return other,
+        }
+        event!(
+            debug,
+            "RENAME_NOREPLACE is not supported",
+            to = to.display()
+        );
+    }
+
+    if !is_dir(&stat_at(from_dir, from)?) {
+        match link_at(from_dir, from, to_dir, to) {
+            Ok(()) => {
+                if let Err(e) = unlink_at(from_dir, from) {
+                    _ = unlink_at(to_dir, to);
+                    return Err(e);
+                }
+                return Ok(());
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Err(e),
+            // Hard links are not supported either (vfat, for example)
+            Err(e) => event!(
+                debug,
+                "Failed to hard link",
+                from = from.display(),
+                error = e
+            ),
+        }
+    }
+
+    if stat_at(to_dir, to).is_ok() {
+        return Err(io::Error::from_raw_os_error(libc::EEXIST));
+    }
+    rename_at(from_dir, from, to_dir, to)
+}
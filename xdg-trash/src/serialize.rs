@@ -0,0 +1,132 @@
+//! `serde` support, enabled by the `serde` feature.
+//!
+//! [`Trash`] and [`TrashFile`] are serialized as flat views of their public accessors,
+//! they can't be deserialized as they only make sense for trashcans that exist on this system.
+//! [`TrashInfo`] goes through [`TrashInfo::builder`] when deserialized, so extension keys are validated.
+//!
+//! Paths are serialized as strings, with bytes that aren't valid UTF-8 replaced by `U+FFFD`.
+//! A [`TrashInfo`] with such a path therefore doesn't deserialize to the same path.
+
+use crate::{Trash, TrashFile, TrashInfo};
+use chrono::NaiveDateTime;
+use serde::{
+    de::{MapAccess, Visitor},
+    ser::{SerializeMap, SerializeStruct},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+impl Serialize for Trash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Trash", 7)?;
+        s.serialize_field("trash_type", &self.trash_type())?;
+        s.serialize_field("trash_dir", &LossyPath(self.trash_dir()))?;
+        s.serialize_field("info_dir", &LossyPath(self.info_dir()))?;
+        s.serialize_field("files_dir", &LossyPath(self.files_dir()))?;
+        s.serialize_field("mount_root", &LossyPath(self.mount_root()))?;
+        s.serialize_field("device", &self.device())?;
+        s.serialize_field("owner", &self.owner())?;
+        s.end()
+    }
+}
+
+impl Serialize for TrashFile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("TrashFile", 7)?;
+        s.serialize_field("id", &self.id())?;
+        s.serialize_field("original_path", &LossyPath(&self.original_path()))?;
+        s.serialize_field("deleted_at", &self.deleted_at())?;
+        s.serialize_field("info_path", &LossyPath(&self.info_filepath()))?;
+        s.serialize_field("files_path", &LossyPath(&self.files_filepath()))?;
+        s.serialize_field("size", &self.known_size())?;
+        s.serialize_field("extra", &Extra(self.extra()))?;
+        s.end()
+    }
+}
+
+impl Serialize for TrashInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("TrashInfo", 3)?;
+        s.serialize_field("path", &LossyPath(self.path()))?;
+        s.serialize_field("deleted_at", &self.deleted_at())?;
+        s.serialize_field("extra", &Extra(self.extra()))?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for TrashInfo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Fields {
+            path: PathBuf,
+            deleted_at: NaiveDateTime,
+            #[serde(default)]
+            extra: ExtraBuf,
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        fields
+            .extra
+            .0
+            .into_iter()
+            .fold(
+                TrashInfo::builder(fields.path).deleted_at(fields.deleted_at),
+                |builder, (key, value)| builder.extra(key, value),
+            )
+            .build()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// A path serialized as a string, even if it isn't valid UTF-8
+struct LossyPath<'a>(&'a Path);
+
+impl Serialize for LossyPath<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_string_lossy())
+    }
+}
+
+/// Extension keys, serialized as a map that keeps their order
+struct Extra<'a>(&'a [(String, String)]);
+
+impl Serialize for Extra<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+#[derive(Default)]
+struct ExtraBuf(Vec<(String, String)>);
+
+impl<'de> Deserialize<'de> for ExtraBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ExtraVisitor;
+
+        impl<'de> Visitor<'de> for ExtraVisitor {
+            type Value = ExtraBuf;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of extension keys")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut extra = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    extra.push(entry);
+                }
+                Ok(ExtraBuf(extra))
+            }
+        }
+
+        deserializer.deserialize_map(ExtraVisitor)
+    }
+}
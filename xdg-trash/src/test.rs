@@ -1,9 +1,14 @@
-use crate::{Trash, UnifiedTrash};
+use crate::{ErrorKind, MountWatcher, Progress, RawEntry, Trash, TrashType, UnifiedTrash};
 use dircpy::copy_dir;
 use std::{
-    fs,
+    collections::HashSet,
+    fs::{self, File},
+    ops::ControlFlow,
     path::{Path, PathBuf},
     rc::Rc,
+    sync::mpsc,
+    thread,
+    time::{Duration, SystemTime},
 };
 use tempdir::TempDir;
 
@@ -50,7 +55,12 @@ fn test_empty() {
 
     assert_eq!(unified.list().collect::<Vec<_>>().len(), 10);
 
-    unified.empty().unwrap().for_each(|_| {});
+    let report = unified.empty();
+    assert!(report.failed.is_empty());
+    assert_eq!(report.removed.len(), 10);
+    assert!(files.iter().all(|x| report.removed.contains(x)));
+    assert_eq!(report.trashes.len(), 2);
+    assert!(report.trashes.iter().all(|x| x.removed == 5));
 
     assert_eq!(unified.list().collect::<Vec<_>>().len(), 0);
 }
@@ -216,3 +226,1149 @@ fn test_put_list_remove() {
         assert_eq!(fs::read_dir(trash.info_dir()).unwrap().count(), 0);
     });
 }
+
+#[test]
+fn test_index_tracks_changes() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let f1 = dirs[0].join("Text File.txt");
+    let f2 = dirs[0].join("trash1.pdf");
+
+    t1.clone().put(&f1).unwrap();
+
+    // Without an index on disk, it is only built in memory unless asked for
+    let index_path = t1.trash_dir().join(".xdg-trash-index");
+    assert_eq!(t1.clone().existing_index().unwrap().entries().len(), 1);
+    assert!(!index_path.exists());
+
+    let index = t1.clone().index().unwrap();
+    assert!(index_path.exists());
+    assert_eq!(index.entries().len(), 1);
+    let id = index.entries()[0].id().to_owned();
+    assert_eq!(index.find(&id).count(), 1);
+    assert_eq!(index.find(f1.to_str().unwrap()).count(), 1);
+
+    // Changes made by this crate are appended to the index
+    t1.clone().put(&f2).unwrap();
+    let index = t1.clone().index().unwrap();
+    assert_eq!(index.entries().len(), 2);
+
+    index
+        .into_entries()
+        .into_iter()
+        .find(|x| x.original_path() == f1)
+        .unwrap()
+        .into_trash_file()
+        .restore(false)
+        .unwrap();
+    assert!(f1.exists());
+
+    let index = t1.clone().index().unwrap();
+    assert_eq!(index.entries().len(), 1);
+    assert_eq!(index.entries()[0].original_path(), f2);
+}
+
+#[test]
+fn test_index_reconciles_external_changes() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let f1 = dirs[0].join("Text File.txt");
+    let f2 = dirs[0].join("trash1.pdf");
+
+    t1.clone().put(&f1).unwrap();
+    t1.clone().put(&f2).unwrap();
+    assert_eq!(t1.clone().index().unwrap().entries().len(), 2);
+
+    // Simulate another program removing an entry behind our back
+    let removed = t1.clone().list().unwrap().next().unwrap().unwrap();
+    fs::remove_file(removed.info_filepath()).unwrap();
+    fs::remove_file(removed.files_filepath()).unwrap();
+
+    let index = t1.clone().index().unwrap();
+    assert_eq!(index.entries().len(), 1);
+    assert_ne!(index.entries()[0].original_path(), removed.original_path());
+}
+
+#[test]
+fn test_index_never_follows_symlinks() {
+    use std::os::unix::fs::symlink;
+
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let index_path = t1.trash_dir().join(".xdg-trash-index");
+    let victim = dirs[1].join("victim");
+    fs::write(&victim, "secret").unwrap();
+
+    t1.clone().put(&dirs[0].join("Text File.txt")).unwrap();
+    t1.clone().index().unwrap();
+
+    // Changes are never appended through a symlinked index
+    fs::remove_file(&index_path).unwrap();
+    symlink(&victim, &index_path).unwrap();
+    t1.clone().put(&dirs[0].join("trash1.pdf")).unwrap();
+    assert_eq!(fs::read_to_string(&victim).unwrap(), "secret");
+
+    // A symlinked index is never read, but replaced
+    assert_eq!(t1.clone().existing_index().unwrap().entries().len(), 2);
+    assert_eq!(fs::read_to_string(&victim).unwrap(), "secret");
+    assert!(!fs::symlink_metadata(&index_path).unwrap().is_symlink());
+
+    // Nor is the index written through a symlinked temporary file
+    fs::remove_file(&index_path).unwrap();
+    symlink(&victim, t1.trash_dir().join(".xdg-trash-index.tmp")).unwrap();
+    assert_eq!(t1.clone().index().unwrap().entries().len(), 2);
+    assert_eq!(fs::read_to_string(&victim).unwrap(), "secret");
+    assert!(!fs::symlink_metadata(&index_path).unwrap().is_symlink());
+}
+
+#[test]
+fn test_entries_are_lazy() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let unified = UnifiedTrash::with_trashcans(trashes.iter().cloned());
+    let f1 = dirs[0].join("Text File.txt");
+
+    trashes[0].clone().put(&f1).unwrap();
+    let entry = unified.entries().next().unwrap().unwrap();
+    assert_eq!(entry.name(), "Text File.txt");
+    assert!(entry.has_payload());
+
+    // A broken trashinfo file only fails once the entry is loaded
+    fs::write(entry.info_filepath(), "garbage").unwrap();
+    assert_eq!(unified.entries().count(), 1);
+    assert!(entry.load().is_err());
+}
+
+#[test]
+fn test_list_raw_reports_broken_entries() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+
+    let valid = t1.clone().put(&dirs[0].join("Text File.txt")).unwrap();
+    let no_payload = t1.clone().put(&dirs[0].join("trash1.pdf")).unwrap();
+    let unparseable = t1.clone().put(&dirs[0].join("some dir")).unwrap();
+    fs::remove_file(no_payload.files_filepath()).unwrap();
+    fs::write(unparseable.info_filepath(), b"\xff garbage").unwrap();
+    fs::write(t1.files_dir().join("orphan"), "no info").unwrap();
+
+    let mut raw = t1
+        .list_raw()
+        .unwrap()
+        .collect::<crate::Result<Vec<_>>>()
+        .unwrap();
+    raw.sort_by_key(|x| x.entry().name().to_owned());
+    let names = raw
+        .iter()
+        .map(|x| x.entry().name().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Text File.txt", "orphan", "some dir", "trash1.pdf"]);
+
+    assert!(
+        matches!(&raw[3], RawEntry::MissingPayload { original_path, .. }
+        if *original_path == no_payload.original_path())
+    );
+    assert!(matches!(&raw[0], RawEntry::Valid(x) if x.original_path() == valid.original_path()));
+    assert!(matches!(&raw[1], RawEntry::MissingInfo { .. }));
+    assert!(matches!(&raw[2], RawEntry::Unparseable { raw, error, .. }
+        if raw.starts_with(b"\xff") && error.kind() == ErrorKind::InvalidTrashinfo));
+}
+
+#[test]
+fn test_removal_is_staged_and_resumed() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let expunged = t1.trash_dir().join("expunged");
+
+    // Leftovers of an interrupted removal
+    fs::create_dir_all(expunged.join("leftover/nested")).unwrap();
+    fs::write(expunged.join("leftover/nested/file"), "half deleted").unwrap();
+
+    let trashed = t1.clone().put(&dirs[0].join("some dir")).unwrap();
+    trashed.remove().unwrap();
+    assert_eq!(fs::read_dir(&expunged).unwrap().count(), 0);
+    assert_eq!(t1.clone().list_raw().unwrap().count(), 0);
+
+    // If the info file can't be removed, the entry keeps it's payload
+    let kept = dirs[0].join("kept");
+    fs::write(&kept, "keep me").unwrap();
+    let trashed = t1.clone().put(&kept).unwrap();
+    fs::remove_file(trashed.info_filepath()).unwrap();
+    fs::create_dir(trashed.info_filepath()).unwrap();
+    let (trashed, _) = trashed.remove().unwrap_err();
+    assert_eq!(fs::read(trashed.files_filepath()).unwrap(), b"keep me");
+    assert_eq!(fs::read_dir(&expunged).unwrap().count(), 0);
+    fs::remove_dir(trashed.info_filepath()).unwrap();
+    fs::remove_file(trashed.files_filepath()).unwrap();
+
+    t1.clone().put(&dirs[0].join("Text File.txt")).unwrap();
+    t1.clone().put(&dirs[0].join("trash1.pdf")).unwrap();
+    fs::write(t1.files_dir().join("orphan"), "no info").unwrap();
+    fs::write(expunged.join("leftover"), "again").unwrap();
+
+    let report = t1.clone().empty();
+    assert!(report.failed.is_empty());
+    assert_eq!(report.removed.len(), 3);
+    assert!(report.removed.contains(&t1.files_dir().join("orphan")));
+    assert_eq!(fs::read_dir(t1.files_dir()).unwrap().count(), 0);
+    assert_eq!(fs::read_dir(t1.info_dir()).unwrap().count(), 0);
+    assert_eq!(fs::read_dir(&expunged).unwrap().count(), 0);
+}
+
+#[test]
+fn test_progress_and_cancellation() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let mut unified = UnifiedTrash::with_trashcans(trashes.iter().cloned());
+    let files = ["Text File.txt", "trash1.pdf", "some dir"].map(|x| dirs[0].join(x));
+    let expected = files
+        .iter()
+        .map(|x| crate::trash::apparent_size(x).unwrap())
+        .sum::<u64>();
+
+    // Cancel after the first file
+    let results = unified.put_all(&files, &mut |p: &Progress| {
+        assert_eq!(p.items_total, 3);
+        if p.items_done < 1 {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    });
+    assert!(results[0].is_ok());
+    assert!(results[1..]
+        .iter()
+        .all(|x| x.as_ref().unwrap_err().kind() == ErrorKind::Cancelled));
+    assert!(files[1].exists());
+
+    // Stop at the first failure, reporting the size of the trashed files
+    let missing = dirs[0].join("missing");
+    let mut last = Progress::default();
+    let results = unified.put_all(&[&files[1], &missing, &files[2]], &mut |p: &Progress| {
+        last = *p;
+        if p.items_failed == 0 {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    });
+    assert!(results[0].is_ok());
+    assert_ne!(
+        results[1].as_ref().unwrap_err().kind(),
+        ErrorKind::Cancelled
+    );
+    assert_eq!(
+        results[2].as_ref().unwrap_err().kind(),
+        ErrorKind::Cancelled
+    );
+    assert_eq!((last.items_done, last.items_failed), (2, 1));
+    assert_eq!(
+        last.bytes_done,
+        crate::trash::apparent_size(&results[0].as_ref().unwrap().files_filepath()).unwrap()
+    );
+    assert!(files[2].exists());
+
+    unified.put(&files[2]).unwrap();
+
+    let size = unified
+        .size_with_progress(&mut |_: &Progress| ControlFlow::Continue(()))
+        .unwrap();
+    assert_eq!(size, expected);
+
+    // Cancelling leaves the remaining entries untouched
+    let report = unified.empty_with_progress(&mut |p: &Progress| {
+        if p.items_done < 2 {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    });
+    assert!(report.cancelled);
+    assert_eq!(report.removed.len(), 2);
+    assert_eq!(unified.list().filter_map(Result::ok).count(), 1);
+
+    let mut last = Progress::default();
+    let report = unified.empty_with_progress(&mut |p: &Progress| {
+        last = *p;
+        ControlFlow::Continue(())
+    });
+    assert_eq!(report.removed.len(), 1);
+    assert_eq!(last.items_done, 1);
+    assert_eq!(last.bytes_done, report.bytes_freed);
+    assert_eq!(unified.list().count(), 0);
+}
+
+#[test]
+fn test_remove_where() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let mut unified = UnifiedTrash::with_trashcans(trashes.iter().cloned());
+    let keep = dirs[0].join("Text File.txt");
+    let remove = dirs[1].join("Text File.txt");
+    unified.put(&keep).unwrap();
+    unified.put(&remove).unwrap();
+    let broken = unified.put(dirs[0].join("trash1.pdf")).unwrap();
+    fs::write(broken.info_filepath(), "garbage").unwrap();
+
+    let report = unified.remove_where(|file| file.original_path() == remove);
+    assert_eq!(report.removed, [remove]);
+    assert!(report.failed.is_empty());
+    assert!(report.bytes_freed > 0);
+    assert_eq!(report.trashes[0].removed, 0);
+    assert_eq!(report.trashes[1].removed, 1);
+
+    // Broken entries are never matched
+    let remaining = unified
+        .list_raw()
+        .collect::<crate::Result<Vec<_>>>()
+        .unwrap();
+    assert_eq!(remaining.len(), 2);
+    assert!(remaining
+        .iter()
+        .any(|x| matches!(x, RawEntry::Valid(f) if f.original_path() == keep)));
+}
+
+#[test]
+fn test_locking_and_fix_grace_period() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let trashed = t1.clone().put(&dirs[0].join("Text File.txt")).unwrap();
+
+    // Another process (simulated by a thread) has to wait for the lock
+    let lock = t1.lock().unwrap();
+    let (tx, rx) = mpsc::channel();
+    let mount = dirs[0].clone();
+    let handle = thread::spawn(move || {
+        let other = Trash::find_user_trash(mount).unwrap();
+        let _lock = other.lock().unwrap();
+        tx.send(()).unwrap();
+    });
+    assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+    drop(lock);
+    rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    handle.join().unwrap();
+
+    // A put in progress, the payload is not moved yet
+    fs::remove_file(trashed.files_filepath()).unwrap();
+    assert_eq!(t1.clone().fix().unwrap(), 0);
+    assert!(trashed.info_filepath().exists());
+
+    let old = SystemTime::now() - Duration::from_secs(120);
+    File::options()
+        .write(true)
+        .open(trashed.info_filepath())
+        .unwrap()
+        .set_modified(old)
+        .unwrap();
+    assert_eq!(t1.fix().unwrap(), 1);
+    assert!(!trashed.info_filepath().exists());
+}
+
+#[test]
+fn test_put_and_restore_never_overwrite() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let f1 = dirs[0].join("Text File.txt");
+
+    // A payload without trashinfo file, for example from a put in progress
+    t1.clone().create_dirs().unwrap();
+    let orphan = t1.files_dir().join("Text File.txt");
+    fs::write(&orphan, "orphan").unwrap();
+
+    let trashed = t1.clone().put(&f1).unwrap();
+    assert_ne!(trashed.files_filepath(), orphan);
+    assert_eq!(fs::read_to_string(&orphan).unwrap(), "orphan");
+
+    fs::create_dir(&f1).unwrap();
+    let (trashed, e) = trashed.restore(false).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::Conflict);
+    assert!(f1.is_dir());
+    assert!(trashed.files_filepath().is_file());
+}
+
+#[test]
+fn test_error_kinds() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let f1 = dirs[0].join("Text File.txt");
+
+    let trashed = t1.clone().put(&f1).unwrap();
+    fs::write(&f1, "in the way").unwrap();
+    let (trashed, e) = trashed.restore(false).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::Conflict);
+    assert_eq!(e.path(), Some(f1.as_path()));
+
+    fs::remove_file(trashed.files_filepath()).unwrap();
+    let payload = trashed.files_filepath();
+    let (_, e) = trashed.remove().unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::NotFound);
+    assert_eq!(e.path(), Some(payload.as_path()));
+
+    let missing = dirs[0].join("does not exist");
+    let e = t1.put(&missing).unwrap_err();
+    assert!(matches!(e, crate::Error::FailedToTrashFile(..)));
+    assert_eq!(e.kind(), ErrorKind::NotFound);
+    assert_eq!(e.path(), Some(missing.as_path()));
+}
+
+#[test]
+fn test_discovery_is_read_only() {
+    let (_tmpdir, dirs, _) = prepare_testdir();
+    let mount = dirs[0].join("some dir");
+    let trash_dir = mount.join(format!(".Trash-{}", unsafe { libc::getuid() }));
+
+    // A partial trash, missing both the info and files directories
+    fs::create_dir(&trash_dir).unwrap();
+    let trash = Rc::new(Trash::find_user_trash(mount.clone()).unwrap());
+    assert_eq!(trash.clone().list().unwrap().count(), 0);
+    assert_eq!(trash.clone().index().unwrap().entries().len(), 0);
+    assert_eq!(trash.clone().fix().unwrap(), 0);
+    assert!(!trash.info_dir().exists());
+    assert!(!trash.files_dir().exists());
+
+    // The directories are only created once something gets trashed
+    let file = mount.join("file");
+    fs::write(&file, "").unwrap();
+    trash.clone().put(&file).unwrap();
+    assert!(trash.info_dir().exists());
+    assert_eq!(trash.list().unwrap().count(), 1);
+}
+
+#[test]
+fn test_refresh_drops_unmounted_trashes() {
+    let (tmpdir, dirs, trashes) = prepare_testdir();
+    let mut unified = UnifiedTrash::with_trashcans(trashes.iter().cloned());
+    let f1 = dirs[1].join("Text File.txt");
+    let trashed = trashes[1].clone().put(&f1).unwrap();
+
+    // Simulate the filesystem of the second trash disappearing
+    let moved = tmpdir.path().join("moved");
+    fs::rename(&dirs[1], &moved).unwrap();
+
+    let summary = unified.refresh().unwrap();
+    assert_eq!(summary.removed.len(), 1);
+    assert_eq!(summary.removed[0].mount_root(), dirs[1]);
+    assert!(!summary.removed[0].is_available());
+
+    // Only the first trash is left, next to the trashes found on the mounted filesystems
+    let known = |unified: &UnifiedTrash| {
+        unified
+            .known_trashes
+            .iter()
+            .map(|x| x.info_dir().to_owned())
+            .collect::<HashSet<_>>()
+    };
+    let expected = summary
+        .added
+        .iter()
+        .chain([&trashes[0]])
+        .map(|x| x.info_dir().to_owned())
+        .collect::<HashSet<_>>();
+    assert_eq!(known(&unified), expected);
+    assert!(unified
+        .list()
+        .all(|x| x.unwrap().trash().mount_root() != dirs[1]));
+
+    // Files of the dropped trash fail cleanly
+    let (_, e) = trashed.restore(false).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::NotFound);
+
+    // Nothing changed since the last refresh
+    let summary = unified.refresh().unwrap();
+    assert!(summary.added.is_empty());
+    assert!(summary.removed.is_empty());
+    assert_eq!(known(&unified), expected);
+
+    // The mount table doesn't change while the tests run
+    assert!(!MountWatcher::new().unwrap().changed().unwrap());
+}
+
+#[test]
+fn test_trashes_of_other_users() {
+    use crate::{list_trashes_of, User};
+    use std::os::unix::fs::{chown, MetadataExt};
+
+    // Managing the trashes of other users requires root
+    if unsafe { libc::geteuid() } != 0 {
+        return;
+    }
+
+    assert_eq!(User::from_name("root").unwrap().uid, 0);
+    assert_eq!(
+        User::from_name("no-such-user").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+    assert!(User::all().unwrap().iter().any(|x| x.uid == 0));
+    let e = crate::Error::UserLookup(std::io::Error::from_raw_os_error(libc::EACCES));
+    assert_eq!(e.kind(), ErrorKind::PermissionDenied);
+
+    let (tmpdir, dirs, _) = prepare_testdir();
+    let (uid, gid) = (4242, 4343);
+    let user = User {
+        name: "someone".to_owned(),
+        uid,
+        gid,
+        home_dir: tmpdir.path().join("home"),
+    };
+
+    // A trash of the user with one entry, as if they trashed the file themselves
+    let trash_dir = dirs[0].join(format!(".Trash-{uid}"));
+    for dir in ["info", "files"] {
+        fs::create_dir_all(trash_dir.join(dir)).unwrap();
+        chown(trash_dir.join(dir), Some(uid), Some(gid)).unwrap();
+    }
+    chown(&trash_dir, Some(uid), Some(gid)).unwrap();
+    let payload = trash_dir.join("files").join("note.txt");
+    fs::write(&payload, "").unwrap();
+    chown(&payload, Some(uid), Some(gid)).unwrap();
+    fs::write(
+        trash_dir.join("info").join("note.txt.trashinfo"),
+        "[Trash Info]\nPath=sub/dir/note.txt\nDeletionDate=2024-04-10T14:30:00\n",
+    )
+    .unwrap();
+
+    let trash = Rc::new(Trash::find_user_trash_of(dirs[0].clone(), uid).unwrap());
+    assert_eq!(trash.owner(), uid);
+    let files = trash
+        .clone()
+        .list()
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].trash().owner(), uid);
+
+    // The restored file and the recreated parents belong to the user, not to root
+    let restored = files.into_iter().next().unwrap().restore(false).unwrap();
+    assert_eq!(restored, dirs[0].join("sub/dir/note.txt"));
+    for path in [&restored, &dirs[0].join("sub/dir"), &dirs[0].join("sub")] {
+        let meta = fs::symlink_metadata(path).unwrap();
+        assert_eq!((meta.uid(), meta.gid()), (uid, gid), "{}", path.display());
+    }
+    let lock = fs::symlink_metadata(trash_dir.join(".xdg-trash-lock")).unwrap();
+    assert_eq!(lock.uid(), uid);
+
+    // A trash that isn't owned by the user is not used
+    fs::create_dir(dirs[1].join(format!(".Trash-{uid}"))).unwrap();
+    let other = Trash::find_user_trash_of(dirs[1].clone(), uid).unwrap();
+    assert!(matches!(
+        other.purge_expunged(),
+        Err(crate::Error::NotOwned(_))
+    ));
+
+    // Only existing trashes are listed, the home trash is taken from the passwd entry
+    assert!(list_trashes_of(std::slice::from_ref(&user))
+        .unwrap()
+        .is_empty());
+    let home_trash = Trash::find_home_trash_of(&user).unwrap();
+    fs::create_dir_all(home_trash.info_dir()).unwrap();
+    let trashes = list_trashes_of(&[user.clone(), user]).unwrap();
+    assert_eq!(trashes.len(), 1);
+    assert_eq!(trashes[0].info_dir(), home_trash.info_dir());
+    assert_eq!(trashes[0].owner(), uid);
+}
+
+#[test]
+fn test_put_as_invoking_user() {
+    use crate::User;
+    use std::os::unix::fs::MetadataExt;
+
+    // Handing files over to another user requires root
+    if unsafe { libc::geteuid() } != 0 {
+        return;
+    }
+
+    let (tmpdir, dirs, _) = prepare_testdir();
+    let owned_by = |path: &Path| fs::symlink_metadata(path).unwrap().uid();
+    // The group is taken from the passwd database, which doesn't know this user
+    let user = User {
+        name: "someone".to_owned(),
+        uid: 4242,
+        gid: 4343,
+        home_dir: tmpdir.path().join("home"),
+    };
+    fs::create_dir(&user.home_dir).unwrap();
+    let file = user.home_dir.join("file");
+    fs::write(&file, "").unwrap();
+
+    // The home trash and it's parents are created for the user
+    let mut unified = UnifiedTrash::as_user(&user).unwrap();
+    let trashed = unified.put(&file).unwrap();
+    assert_eq!(trashed.trash().owner(), user.uid);
+    assert_eq!(trashed.trash().trash_type(), TrashType::Home);
+    for path in [
+        user.home_dir.join(".local"),
+        user.home_dir.join(".local/share/Trash"),
+        trashed.trash().files_dir().to_owned(),
+        trashed.info_filepath(),
+    ] {
+        assert_eq!(owned_by(&path), user.uid, "{}", path.display());
+    }
+    // The trashed file itself keeps it's owner
+    assert_eq!(owned_by(&trashed.files_filepath()), 0);
+    assert_eq!(owned_by(&user.home_dir), 0);
+
+    // Per-mount trashes are created for the user as well
+    let trash = Rc::new(Trash::create_user_trash_of(dirs[0].clone(), user.uid).unwrap());
+    let trashed = trash.put(&dirs[0].join("Text File.txt")).unwrap();
+    for path in [
+        dirs[0].join(".Trash-4242"),
+        trashed.trash().info_dir().to_owned(),
+        trashed.info_filepath(),
+    ] {
+        assert_eq!(owned_by(&path), user.uid, "{}", path.display());
+    }
+}
+
+#[test]
+fn test_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let mode = |path: &Path| fs::symlink_metadata(path).unwrap().permissions().mode() & 0o7777;
+
+    // Everything created for a put is private
+    let trashed = trashes[0].clone().put(&dirs[0].join("trash1.pdf")).unwrap();
+    for dir in [trashes[0].info_dir(), trashes[0].files_dir()] {
+        assert_eq!(mode(dir), 0o700, "{}", dir.display());
+    }
+    assert_eq!(mode(&trashed.info_filepath()), 0o600);
+    assert!(trashes[0].check_permissions(false).unwrap().is_empty());
+
+    // A trash created by another implementation with the default umask
+    fs::set_permissions(trashes[0].info_dir(), fs::Permissions::from_mode(0o755)).unwrap();
+    fs::set_permissions(trashed.info_filepath(), fs::Permissions::from_mode(0o644)).unwrap();
+    let issues = trashes[0].check_permissions(false).unwrap();
+    assert_eq!(issues.len(), 2);
+    assert!(issues.iter().all(|x| !x.fixed));
+    assert_eq!(issues[0].path, trashes[0].info_dir());
+    assert_eq!((issues[0].mode, issues[0].expected), (0o755, 0o700));
+    assert_eq!(issues[1].path, trashed.info_filepath());
+    assert_eq!((issues[1].mode, issues[1].expected), (0o644, 0o600));
+
+    let unified = UnifiedTrash::with_trashcans(trashes.iter().cloned());
+    assert_eq!(unified.check_permissions(true).unwrap(), {
+        let mut fixed = issues;
+        fixed.iter_mut().for_each(|x| x.fixed = true);
+        fixed
+    });
+    assert_eq!(mode(trashes[0].info_dir()), 0o700);
+    assert_eq!(mode(&trashed.info_filepath()), 0o600);
+    assert!(unified.check_permissions(false).unwrap().is_empty());
+
+    // The trash itself and not yet existing trashes
+    assert_eq!(
+        mode(&dirs[1].join(format!(".Trash-{}", unsafe { libc::getuid() }))),
+        0o700
+    );
+    assert!(trashes[1].check_permissions(false).unwrap().is_empty());
+}
+
+#[test]
+fn test_put_records_physical_parent() {
+    use std::os::unix::fs::symlink;
+
+    let (tmpdir, dirs, trashes) = prepare_testdir();
+    let nested = dirs[0].join("some dir").join("nested");
+    fs::create_dir(&nested).unwrap();
+    let link = dirs[0].join("link");
+    symlink(&nested, &link).unwrap();
+    fs::write(dirs[0].join("some dir").join("file"), "").unwrap();
+
+    // `..` of the symlink is the parent of it's target, not the directory containing the symlink
+    let trashed = trashes[0]
+        .clone()
+        .put(&link.join("..").join("file"))
+        .unwrap();
+    assert_eq!(trashed.trashinfo().path(), Path::new("some dir/file"));
+    assert_eq!(
+        trashed.restore(false).unwrap(),
+        dirs[0].join("some dir").join("file")
+    );
+
+    // A symlink itself is still trashed, not it's target
+    let trashed = trashes[0].clone().put(&link).unwrap();
+    assert_eq!(trashed.trashinfo().path(), Path::new("link"));
+    assert!(nested.exists());
+
+    // Trashes whose location contains a symlink match the physical path of the files
+    let mount_link = tmpdir.path().join("mount-link");
+    symlink(&dirs[1], &mount_link).unwrap();
+    let trash = Trash::open(trashes[1].trash_dir(), &mount_link, true, TrashType::User).unwrap();
+    let mut unified = UnifiedTrash::with_trashcans([Rc::new(trash)].into_iter());
+    let trashed = unified.put_known(mount_link.join("trash1.pdf")).unwrap();
+    assert_eq!(trashed.trashinfo().path(), Path::new("trash1.pdf"));
+}
+
+#[test]
+fn test_home_trash_for_files_outside_of_home() {
+    let (tmpdir, dirs, trashes) = prepare_testdir();
+
+    // A home trash on the same device as the files, but they aren't inside of it's home directory
+    let home = tmpdir.path().join("home");
+    let home_trash_dir = home.join(".local/share/Trash");
+    fs::create_dir_all(&home_trash_dir).unwrap();
+    let home_trash = Rc::new(Trash::open(&home_trash_dir, &home, false, TrashType::Home).unwrap());
+
+    let mut unified = UnifiedTrash::with_trashcans([home_trash.clone()].into_iter());
+    let file = dirs[0].join("Text File.txt");
+    let trashed = unified.put_known(&file).unwrap();
+    assert_eq!(trashed.trash().info_dir(), home_trash.info_dir());
+    assert_eq!(trashed.original_path(), file);
+
+    // A matching trash is still preferred
+    let mut unified =
+        UnifiedTrash::with_trashcans([home_trash.clone(), trashes[0].clone()].into_iter());
+    let trashed = unified.put_known(dirs[0].join("trash1.pdf")).unwrap();
+    assert_eq!(trashed.trash().info_dir(), trashes[0].info_dir());
+
+    // Without a home trash on the device, nothing matches
+    let mut unified = UnifiedTrash::with_trashcans(std::iter::empty());
+    let e = unified.put_known(dirs[1].join("trash1.pdf")).unwrap_err();
+    assert!(matches!(e, crate::Error::NoTrashFound));
+}
+
+#[test]
+fn test_read_only_trash_falls_back_to_home_trash() {
+    use std::os::{fd::AsRawFd, unix::fs::PermissionsExt};
+
+    const FS_IMMUTABLE_FL: libc::c_long = 0x10;
+
+    let (tmpdir, dirs, trashes) = prepare_testdir();
+    let home = tmpdir.path().join("home");
+    let home_trash_dir = home.join(".local/share/Trash");
+    fs::create_dir_all(&home_trash_dir).unwrap();
+    let home_trash = Rc::new(Trash::open(&home_trash_dir, &home, false, TrashType::Home).unwrap());
+
+    // Root can write to any directory, so the trash is made immutable instead
+    let read_only = &trashes[0].trash_dir();
+    fs::create_dir_all(trashes[0].info_dir()).unwrap();
+    let set_read_only = |on: bool| {
+        if unsafe { libc::geteuid() } != 0 {
+            let mode = if on { 0o500 } else { 0o700 };
+            fs::set_permissions(read_only, fs::Permissions::from_mode(mode)).unwrap();
+            return true;
+        }
+        let dir = File::open(read_only).unwrap();
+        let mut flags: libc::c_long = 0;
+        if unsafe { libc::ioctl(dir.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) } != 0 {
+            return false;
+        }
+        flags = if on {
+            flags | FS_IMMUTABLE_FL
+        } else {
+            flags & !FS_IMMUTABLE_FL
+        };
+        unsafe { libc::ioctl(dir.as_raw_fd(), libc::FS_IOC_SETFLAGS, &flags) == 0 }
+    };
+    if !set_read_only(true) {
+        // The filesystem doesn't support immutable directories
+        return;
+    }
+
+    let mut unified =
+        UnifiedTrash::with_trashcans([home_trash.clone(), trashes[0].clone()].into_iter());
+    let results = ["Text File.txt", "trash1.pdf"].map(|x| unified.put_known(dirs[0].join(x)));
+    assert!(set_read_only(false));
+
+    for trashed in results {
+        assert_eq!(trashed.unwrap().trash().info_dir(), home_trash.info_dir());
+    }
+}
+
+#[test]
+fn test_examine_mount_reports_rejections() {
+    use crate::{discovery::examine_mount, CandidateStatus, RejectReason};
+    use std::os::unix::fs::PermissionsExt;
+
+    let (_tmpdir, dirs, _) = prepare_testdir();
+    let report = examine_mount(dirs[0].clone(), unsafe { libc::getuid() });
+    assert!(matches!(
+        report.candidates[0].status,
+        CandidateStatus::Rejected(RejectReason::Missing)
+    ));
+    assert!(matches!(
+        report.candidates[1].status,
+        CandidateStatus::Accepted(_)
+    ));
+
+    // An admin trash without the sticky bit is rejected, so the user trash is still used
+    let admin = dirs[0].join(".Trash");
+    fs::create_dir(&admin).unwrap();
+    fs::set_permissions(&admin, fs::Permissions::from_mode(0o777)).unwrap();
+    let report = examine_mount(dirs[0].clone(), unsafe { libc::getuid() });
+    assert_eq!(report.candidates[0].trash_type, TrashType::Admin);
+    assert!(matches!(
+        report.candidates[0].status,
+        CandidateStatus::Rejected(RejectReason::NotSticky)
+    ));
+    assert_eq!(
+        report.accepted().next().unwrap().trash_type(),
+        TrashType::User
+    );
+
+    // Once it is valid, both are used, files are put into the admin trash
+    fs::set_permissions(&admin, fs::Permissions::from_mode(0o1777)).unwrap();
+    let report = examine_mount(dirs[0].clone(), unsafe { libc::getuid() });
+    let accepted = report.accepted().cloned().collect::<Vec<_>>();
+    assert_eq!(accepted.len(), 2);
+    assert_eq!(accepted[0].trash_type(), TrashType::Admin);
+
+    let user_file = dirs[0].join("trash1.pdf");
+    accepted[1].clone().put(&user_file).unwrap();
+
+    let mut unified = UnifiedTrash::with_trashcans(accepted.into_iter().rev());
+    let admin_file = unified.put_known(dirs[0].join("Text File.txt")).unwrap();
+    assert_eq!(admin_file.trash().trash_type(), TrashType::Admin);
+
+    let mut listed = unified
+        .list()
+        .map(|x| x.unwrap().original_path())
+        .collect::<Vec<_>>();
+    listed.sort();
+    assert_eq!(listed, [dirs[0].join("Text File.txt"), user_file]);
+}
+
+#[test]
+fn test_open_custom_trash() {
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let f1 = dirs[0].join("Text File.txt");
+    trashes[0].clone().put(&f1).unwrap();
+
+    // A copy of the trash at a different location resolves relative paths against the new mount root
+    let copy = dirs[1].join("backup");
+    let trash_dir = trashes[0].info_dir().parent().unwrap();
+    copy_dir(trash_dir, &copy).unwrap();
+    let opened = Trash::open(&copy, &dirs[1], true, TrashType::User).unwrap();
+    assert_eq!(opened.info_dir(), copy.join("info"));
+
+    let listed = Rc::new(opened).list().unwrap().next().unwrap().unwrap();
+    assert_eq!(listed.original_path(), dirs[1].join("Text File.txt"));
+
+    let e = Trash::open(dirs[1].join("trash1.pdf"), &dirs[1], true, TrashType::User).unwrap_err();
+    assert!(matches!(e, crate::Error::NotADirectory(_)));
+}
+
+#[test]
+fn test_shared_trash_rejects_symlinks() {
+    use std::os::unix::fs::symlink;
+
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let f1 = dirs[0].join("Text File.txt");
+    let trash_dir = t1.info_dir().parent().unwrap().to_owned();
+    fs::create_dir_all(t1.info_dir()).unwrap();
+
+    // The trash directory itself is swapped for a symlink
+    let elsewhere = dirs[1].join("elsewhere");
+    fs::rename(&trash_dir, &elsewhere).unwrap();
+    symlink(&elsewhere, &trash_dir).unwrap();
+    let e = t1.clone().put(&f1).unwrap_err();
+    assert!(
+        matches!(e, crate::Error::FailedToTrashFile(_, ref x) if matches!(**x, crate::Error::IsSymlink(_)))
+    );
+    assert!(f1.exists());
+
+    // The info directory is swapped for a symlink
+    fs::remove_file(&trash_dir).unwrap();
+    fs::rename(&elsewhere, &trash_dir).unwrap();
+    fs::remove_dir(t1.info_dir()).unwrap();
+    fs::create_dir(&elsewhere).unwrap();
+    symlink(&elsewhere, t1.info_dir()).unwrap();
+    let e = t1.clone().put(&f1).unwrap_err();
+    assert!(
+        matches!(e, crate::Error::FailedToTrashFile(_, ref x) if matches!(**x, crate::Error::IsSymlink(_)))
+    );
+    assert!(f1.exists());
+    assert_eq!(fs::read_dir(&elsewhere).unwrap().count(), 0);
+}
+
+#[test]
+fn test_trashinfo_corpus_round_trips() {
+    use crate::TrashInfo;
+    use chrono::NaiveDateTime;
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    // Written by GNOME (GIO), KDE and python trash-cli
+    let corpus: [(&str, &[u8], &str); 7] = [
+        (
+            "gnome-home",
+            b"/home/user/Documents/My Report (final).odt",
+            "2024-03-14T09:26:53",
+        ),
+        (
+            "gnome-relative",
+            b"Photos/2023/IMG_0001.JPG",
+            "2023-12-24T18:00:02",
+        ),
+        (
+            "gnome-non-utf8",
+            b"/tmp/caf\xe9 latin1",
+            "2022-07-01T00:00:00",
+        ),
+        (
+            "kde-reserved",
+            b"/home/user/Downloads/setup+config&notes.tar.gz",
+            "2023-11-02T18:04:11",
+        ),
+        (
+            "kde-unicode",
+            "/home/user/über café/100% done?.txt".as_bytes(),
+            "2024-01-09T07:45:30",
+        ),
+        (
+            "trash-cli-special",
+            b"/home/user/file with [brackets] #hash=value.txt",
+            "2021-05-17T13:14:15",
+        ),
+        (
+            "trash-cli-unreserved",
+            b"/home/user/.config/app~backup/settings_v2.json",
+            "2020-02-29T23:59:59",
+        ),
+    ];
+
+    for (name, path, deleted_at) in corpus {
+        let file = Path::new("test_trashinfo").join(format!("{name}.trashinfo"));
+        let bytes = fs::read(&file).unwrap();
+        let info = std::str::from_utf8(&bytes)
+            .unwrap()
+            .parse::<TrashInfo>()
+            .unwrap();
+        assert_eq!(info.path(), Path::new(OsStr::from_bytes(path)), "{name}");
+        assert_eq!(
+            info.deleted_at(),
+            deleted_at.parse::<NaiveDateTime>().unwrap(),
+            "{name}"
+        );
+
+        let mut written = Vec::new();
+        info.write_to(&mut written).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&written),
+            String::from_utf8_lossy(&bytes),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn test_trashinfo_extra_keys() {
+    use crate::TrashInfo;
+
+    let file =
+        "[Trash Info]\nPath=/a\nX-Zeta=1\nDeletionDate=2024-01-01T00:00:00\nX-Alpha=a=b\nFoo=bar\n";
+    let info = TrashInfo::parse(file).unwrap();
+    assert_eq!(
+        info.extra(),
+        [
+            ("X-Zeta".to_owned(), "1".to_owned()),
+            ("X-Alpha".to_owned(), "a=b".to_owned())
+        ]
+    );
+    assert_eq!(
+        info.serialize(),
+        "[Trash Info]\nPath=/a\nDeletionDate=2024-01-01T00:00:00\nX-Zeta=1\nX-Alpha=a=b\n"
+    );
+    assert_eq!(TrashInfo::parse(&info.serialize()).unwrap(), info);
+
+    let e = TrashInfo::builder("/a").extra("Trashed-By", "me").build();
+    assert!(matches!(e, Err(crate::Error::InvalidExtraKey(_))));
+    let e = TrashInfo::builder("/a").extra("X-Note", "a\nb").build();
+    assert!(matches!(e, Err(crate::Error::InvalidExtraValue(_))));
+
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let f1 = dirs[0].join("Text File.txt");
+    let extra = [("X-Trashed-By", "cleanup"), ("X-Ticket", "OPS-1234")];
+    let e = t1
+        .clone()
+        .put_with_extra(&f1, &[("Ticket", "OPS-1234")])
+        .unwrap_err();
+    assert!(matches!(e.root_cause(), crate::Error::InvalidExtraKey(_)));
+    assert!(f1.exists());
+    assert_eq!(fs::read_dir(t1.info_dir()).map_or(0, Iterator::count), 0);
+
+    let trashed = t1.clone().put_with_extra(&f1, &extra).unwrap();
+    assert_eq!(trashed.get_extra("X-Ticket"), Some("OPS-1234"));
+
+    let listed = t1.clone().list().unwrap().next().unwrap().unwrap();
+    assert_eq!(listed.extra(), trashed.extra());
+
+    // The index keeps them as well
+    t1.clone().index().unwrap();
+    let indexed = t1.clone().index().unwrap().into_entries().remove(0);
+    assert_eq!(indexed.into_trash_file().extra(), trashed.extra());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde() {
+    use crate::TrashInfo;
+    use serde_json::json;
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let f1 = dirs[0].join("Text File.txt");
+    let file = t1
+        .clone()
+        .put_with_extra(&f1, &[("X-B", "2"), ("X-A", "1")])
+        .unwrap();
+
+    let value = serde_json::to_value(&file).unwrap();
+    assert_eq!(value["id"], json!(file.id()));
+    assert_eq!(value["original_path"], json!(f1));
+    assert_eq!(value["files_path"], json!(file.files_filepath()));
+    assert!(serde_json::to_string(&file)
+        .unwrap()
+        .contains(r#""extra":{"X-B":"2","X-A":"1"}"#));
+
+    let value = serde_json::to_value(&*t1).unwrap();
+    assert_eq!(value["trash_type"], json!("User"));
+    assert_eq!(value["info_dir"], json!(t1.info_dir()));
+    assert_eq!(
+        serde_json::from_value::<TrashType>(value["trash_type"].clone()).unwrap(),
+        TrashType::User
+    );
+
+    // TrashInfo round trips and keeps the order of extension keys
+    let info = file.trashinfo();
+    let json = serde_json::to_string(info).unwrap();
+    assert_eq!(&serde_json::from_str::<TrashInfo>(&json).unwrap(), info);
+
+    let invalid = r#"{"path":"/a","deleted_at":"2024-01-01T00:00:00","extra":{"B":"1"}}"#;
+    assert!(serde_json::from_str::<TrashInfo>(invalid).is_err());
+
+    // Paths that aren't valid UTF-8 are serialized lossily
+    let non_utf8 = dirs[0].join(OsStr::from_bytes(b"caf\xe9"));
+    File::create(&non_utf8).unwrap();
+    let file = t1.put(&non_utf8).unwrap();
+    let value = serde_json::to_value(&file).unwrap();
+    assert_eq!(value["original_path"], json!(dirs[0].join("caf\u{FFFD}")));
+    assert!(serde_json::to_string(file.trashinfo())
+        .unwrap()
+        .contains("caf\u{FFFD}"));
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_spans() {
+    use std::{
+        collections::HashMap,
+        fmt,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    #[derive(Default)]
+    struct Recorder {
+        spans: Mutex<Vec<(String, HashMap<String, String>)>>,
+        events: Mutex<Vec<HashMap<String, String>>>,
+        entered: Mutex<Vec<u64>>,
+    }
+
+    struct Fields<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.insert(field.name().to_owned(), format!("{value:?}"));
+        }
+    }
+
+    struct Collect(Arc<Recorder>);
+
+    impl Subscriber for Collect {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            let mut fields = HashMap::new();
+            span.record(&mut Fields(&mut fields));
+            let mut spans = self.0.spans.lock().unwrap();
+            spans.push((span.metadata().name().to_owned(), fields));
+            span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+            let mut spans = self.0.spans.lock().unwrap();
+            values.record(&mut Fields(&mut spans[id.into_u64() as usize - 1].1));
+        }
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = HashMap::new();
+            event.record(&mut Fields(&mut fields));
+            // Remember the span the event happened in
+            if let Some(&id) = self.0.entered.lock().unwrap().last() {
+                let name = self.0.spans.lock().unwrap()[id as usize - 1].0.clone();
+                fields.insert("span".to_owned(), name);
+            }
+            self.0.events.lock().unwrap().push(fields);
+        }
+
+        fn enter(&self, id: &span::Id) {
+            self.0.entered.lock().unwrap().push(id.into_u64());
+        }
+
+        fn exit(&self, _: &span::Id) {
+            self.0.entered.lock().unwrap().pop();
+        }
+    }
+
+    let (_tmpdir, dirs, trashes) = prepare_testdir();
+    let t1 = trashes[0].clone();
+    let f1 = dirs[0].join("Text File.txt");
+    fs::create_dir_all(t1.files_dir()).unwrap();
+    fs::copy(&f1, t1.files_dir().join("Text File.txt")).unwrap();
+
+    let recorder = Arc::new(Recorder::default());
+    let orphan = t1.info_dir().join("orphan.trashinfo");
+    tracing::subscriber::with_default(Collect(recorder.clone()), || {
+        let file = t1.clone().put(&f1).unwrap();
+        file.restore(false).unwrap();
+
+        // Listing is lazy, the span has to be entered for every item
+        fs::write(
+            &orphan,
+            "[Trash Info]\nPath=orphan\nDeletionDate=2024-01-01T00:00:00\n",
+        )
+        .unwrap();
+        let listed = t1.clone().list().unwrap();
+        tracing::info!("between list and iteration");
+        assert_eq!(listed.filter(Result::is_err).count(), 1);
+    });
+
+    let spans = recorder.spans.lock().unwrap();
+    let (name, put) = &spans[0];
+    assert_eq!(name, "put");
+    assert_eq!(put["input"], f1.display().to_string());
+    assert_eq!(
+        put["trash"],
+        t1.info_dir().parent().unwrap().display().to_string()
+    );
+    // The name was taken by the payload without an info file
+    assert_eq!(put["name"], "Text File_2.txt");
+    assert!(spans
+        .iter()
+        .any(|(name, fields)| name == "restore"
+            && fields["original_path"] == f1.display().to_string()));
+
+    let events = recorder.events.lock().unwrap();
+    assert!(events.iter().any(
+        |x| x.get("name").map(String::as_str) == Some("Text File.txt")
+            && x["message"].contains("files directory")
+    ));
+    let orphaned = events
+        .iter()
+        .find(|x| x["message"] == "Orphaned trashinfo file")
+        .unwrap();
+    assert_eq!(orphaned["span"], "list");
+    assert_eq!(orphaned["info_path"], orphan.display().to_string());
+    let between = events
+        .iter()
+        .find(|x| x["message"] == "between list and iteration")
+        .unwrap();
+    assert!(!between.contains_key("span"));
+}
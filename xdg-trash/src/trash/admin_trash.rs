@@ -1,5 +1,8 @@
 use super::Trash;
-use crate::trash::TrashType;
+use crate::{
+    error::{FsOp, IoResultExt},
+    trash::TrashType,
+};
 use std::{
     fs,
     os::unix::fs::{MetadataExt, PermissionsExt},
@@ -7,35 +10,43 @@ use std::{
 };
 
 impl Trash {
-    /// Finds an admin-created trashcan (`.Trash`) at the given location
+    /// Finds an admin-created trashcan (`.Trash`) at the given location.
+    ///
+    /// This never creates anything on disk. The users directory inside of
+    /// the admin trash is created on the first [`Trash::put`].
     pub fn find_admin_trash(mount_root: PathBuf) -> crate::Result<Self> {
+        Self::find_admin_trash_of(mount_root, unsafe { libc::getuid() })
+    }
+
+    /// Like [`Trash::find_admin_trash`], but for the user with the id `uid` (`.Trash/{uid}`)
+    pub fn find_admin_trash_of(mount_root: PathBuf, uid: u32) -> crate::Result<Self> {
         let trash_dir = mount_root.join(".Trash");
-        let trash_dir_meta = fs::symlink_metadata(&trash_dir)?;
-        let uid = unsafe { libc::getuid() };
-        let uid = uid.to_string();
+        let trash_dir_meta = fs::symlink_metadata(&trash_dir).fs_err(FsOp::Stat, &trash_dir)?;
 
-        if trash_dir_meta.permissions().mode() & 0o1000 == 0 {
-            log::warn!(
-                "Rejecting admin trash at {} because the sticky bit is not set",
-                trash_dir.display()
+        // Checked first, as the mode of a symlink never has the sticky bit set
+        if trash_dir_meta.is_symlink() {
+            event!(
+                warn,
+                "Rejecting admin trash because it is a symlink",
+                trash = trash_dir.display()
             );
-            return Err(crate::Error::NotSticky(trash_dir));
+            return Err(crate::Error::IsSymlink(trash_dir));
         }
 
-        if trash_dir_meta.is_symlink() {
-            log::warn!(
-                "Rejecting admin trash at {} because it is a symlink",
-                trash_dir.display()
+        if trash_dir_meta.permissions().mode() & 0o1000 == 0 {
+            event!(
+                warn,
+                "Rejecting admin trash because the sticky bit is not set",
+                trash = trash_dir.display()
             );
-            return Err(crate::Error::IsSymlink(trash_dir));
+            return Err(crate::Error::NotSticky(trash_dir));
         }
 
-        let info_dir = trash_dir.join(&uid).join("info");
-        let files_dir = trash_dir.join(&uid).join("files");
-        fs::create_dir_all(&info_dir)?;
-        fs::create_dir_all(&files_dir)?;
+        let user_dir = trash_dir.join(uid.to_string());
+        let info_dir = user_dir.join("info");
+        let files_dir = user_dir.join("files");
 
-        log::debug!("Found admin trash at: {}", trash_dir.display());
+        event!(debug, "Found admin trash", trash = trash_dir.display());
 
         Ok(Self {
             device: trash_dir_meta.dev(),
@@ -45,6 +56,7 @@ impl Trash {
             files_dir,
             trash_type: TrashType::Admin,
             use_relative_path: true,
+            owner: uid,
         })
     }
 }
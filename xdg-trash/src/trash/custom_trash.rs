@@ -0,0 +1,68 @@
+use super::Trash;
+use crate::{
+    error::{FsOp, IoResultExt},
+    trash::TrashType,
+};
+use std::{fs, os::unix::fs::MetadataExt, path::PathBuf};
+
+impl Trash {
+    /// Opens the trash located at `trash_dir` (the directory containing `info` and `files`).
+    ///
+    /// Unlike the other constructors, this doesn't check if the trash is at a location
+    /// where other implementations would look for it, which is useful for trashes copied off
+    /// backups, application-private trashes or test fixtures.
+    ///
+    /// - `mount_root`: relative paths in `.trashinfo` files are resolved against this directory,
+    ///   and only files inside of it can be put into the trash
+    /// - `use_relative_path`: whether files put into this trash should be stored with a path relative to `mount_root`
+    /// - `trash_type`: the type of the trash, which determines it's priority in a [`crate::UnifiedTrash`]
+    ///
+    /// Relative paths are made absolute against the current directory.
+    ///
+    /// # Errors
+    /// Fails if `trash_dir` isn't an existing directory.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::rc::Rc;
+    /// use xdg_trash::{Trash, TrashType};
+    ///
+    /// let trash = Trash::open("/backup/.Trash-1000", "/backup", true, TrashType::User).unwrap();
+    /// for file in Rc::new(trash).list().unwrap().filter_map(Result::ok) {
+    ///     println!("{}", file.original_path().display());
+    /// }
+    /// ```
+    pub fn open(
+        trash_dir: impl Into<PathBuf>,
+        mount_root: impl Into<PathBuf>,
+        use_relative_path: bool,
+        trash_type: TrashType,
+    ) -> crate::Result<Self> {
+        let trash_dir = absolute(trash_dir.into())?;
+        let mount_root = absolute(mount_root.into())?;
+        let trash_dir_meta = fs::metadata(&trash_dir).fs_err(FsOp::Stat, &trash_dir)?;
+        if !trash_dir_meta.is_dir() {
+            return Err(crate::Error::NotADirectory(trash_dir));
+        }
+
+        event!(debug, "Opened trash", trash = trash_dir.display());
+
+        Ok(Self {
+            device: trash_dir_meta.dev(),
+            mount_root: mount_root.clone(),
+            based_on: mount_root,
+            info_dir: trash_dir.join("info"),
+            files_dir: trash_dir.join("files"),
+            trash_type,
+            use_relative_path,
+            owner: unsafe { libc::getuid() },
+        })
+    }
+}
+
+fn absolute(path: PathBuf) -> crate::Result<PathBuf> {
+    if path.is_absolute() {
+        return Ok(path);
+    }
+    std::path::absolute(&path).fs_err(FsOp::CurrentDir, &path)
+}
@@ -0,0 +1,239 @@
+use super::{Owner, Trash, TrashType, DIR_MODE, FILE_MODE};
+use crate::{
+    dirfd::{self, Dir},
+    error::{FsOp, IoResultExt},
+};
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io,
+    os::fd::{AsFd, AsRawFd, OwnedFd},
+    path::Path,
+};
+
+const LOCK_FILE: &str = ".xdg-trash-lock";
+
+/// Open handles to the directories of a trash.
+///
+/// Admin trashes are world-writable, so another user could swap parts of the path to a trash
+/// for symlinks between two operations. All operations on entries therefore go through
+/// these handles, which are opened without following symlinks, instead of through paths.
+#[derive(Debug)]
+pub(crate) struct TrashDirs<'a> {
+    trash: &'a Trash,
+    pub(super) dir: OwnedFd,
+    pub(super) info: OwnedFd,
+    pub(super) files: OwnedFd,
+}
+
+/// An advisory lock on a trash directory, released when dropped.
+///
+/// Held while entries are added or removed, so concurrent processes using this crate never
+/// see an entry halfway through being created or removed. Other implementations don't take
+/// this lock, see [`Trash::fix`] for how their in-flight operations are handled.
+#[derive(Debug)]
+pub(crate) struct TrashLock {
+    _file: Option<File>,
+}
+
+impl Trash {
+    /// Opens the trash directory and it's `info` and `files` directories, creating them
+    /// if `create` is set.
+    ///
+    /// For trashes on shared filesystems ([`TrashType::Admin`] and [`TrashType::User`]),
+    /// the trash directory must not be a symlink and has to be owned by it's user (see [`Trash::owner`]).
+    pub(crate) fn open_dirs(&self, create: bool) -> crate::Result<TrashDirs<'_>> {
+        let trash_dir = self.trash_dir();
+        let shared = self.trash_type != TrashType::Home;
+        let (parent, name) = match (trash_dir.parent(), trash_dir.file_name()) {
+            (Some(parent), Some(name)) => (parent, Path::new(name)),
+            _ => return Err(crate::Error::NotADirectory(trash_dir.to_owned())),
+        };
+
+        // Looked up before anything is created, as the group is taken from the trash directory
+        let owner = if create { self.foreign_owner() } else { None };
+        if create && !shared {
+            super::create_dir_all_owned(parent, 0o777, owner)?;
+        }
+
+        // The admin trash itself is checked again, as it could have been replaced since it was found
+        let parent_fd = if self.trash_type == TrashType::Admin {
+            let fd = open_dir(Dir::Cwd, parent, false, parent)?;
+            let stat = dirfd::fstat(fd.as_fd()).fs_err(FsOp::Stat, parent)?;
+            if stat.st_mode & libc::S_ISVTX == 0 {
+                return Err(crate::Error::NotSticky(parent.to_owned()));
+            }
+            fd
+        } else {
+            open_dir(Dir::Cwd, parent, true, parent)?
+        };
+
+        let dir = open_or_create(
+            Dir::Fd(parent_fd.as_fd()),
+            name,
+            create,
+            owner,
+            !shared,
+            trash_dir,
+        )?;
+        if shared {
+            let stat = dirfd::fstat(dir.as_fd()).fs_err(FsOp::Stat, trash_dir)?;
+            if stat.st_uid != self.owner {
+                return Err(crate::Error::NotOwned(trash_dir.to_owned()));
+            }
+        }
+
+        let info = open_or_create(
+            Dir::Fd(dir.as_fd()),
+            Path::new("info"),
+            create,
+            owner,
+            !shared,
+            &self.info_dir,
+        )?;
+        let files = open_or_create(
+            Dir::Fd(dir.as_fd()),
+            Path::new("files"),
+            create,
+            owner,
+            !shared,
+            &self.files_dir,
+        )?;
+
+        Ok(TrashDirs {
+            trash: self,
+            dir,
+            info,
+            files,
+        })
+    }
+
+    /// Takes the exclusive lock of this trash, blocking until it's available
+    #[cfg(test)]
+    pub(crate) fn lock(&self) -> crate::Result<TrashLock> {
+        self.open_dirs(false)?.lock()
+    }
+}
+
+impl TrashDirs<'_> {
+    pub(crate) fn trash(&self) -> &Trash {
+        self.trash
+    }
+
+    pub(crate) fn dir(&self) -> Dir<'_> {
+        Dir::Fd(self.dir.as_fd())
+    }
+
+    pub(crate) fn info(&self) -> Dir<'_> {
+        Dir::Fd(self.info.as_fd())
+    }
+
+    pub(crate) fn files(&self) -> Dir<'_> {
+        Dir::Fd(self.files.as_fd())
+    }
+
+    /// Takes the exclusive lock of this trash, blocking until it's available.
+    ///
+    /// If the lock file can't be created because the trash is not writable, no lock is taken,
+    /// as the operation is going to fail anyway.
+    pub(crate) fn lock(&self) -> crate::Result<TrashLock> {
+        let path = self.trash.trash_dir().join(LOCK_FILE);
+        let file = match dirfd::open_file_at(
+            Dir::Fd(self.dir.as_fd()),
+            Path::new(LOCK_FILE),
+            libc::O_CREAT | libc::O_WRONLY | libc::O_APPEND,
+            FILE_MODE,
+        ) {
+            Ok(x) => x,
+            Err(e)
+                if matches!(
+                    e.raw_os_error(),
+                    Some(libc::EACCES | libc::EPERM | libc::EROFS)
+                ) =>
+            {
+                event!(debug, "Not locking trash", lock = path.display(), error = e);
+                return Ok(TrashLock { _file: None });
+            }
+            Err(e) => return Err(e).fs_err(FsOp::Create, &path),
+        };
+        self.hand_over(Path::new(LOCK_FILE), &path)?;
+
+        loop {
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+                return Ok(TrashLock { _file: Some(file) });
+            }
+
+            let e = io::Error::last_os_error();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e).fs_err(FsOp::Lock, &path);
+            }
+        }
+    }
+
+    /// Hands the entry `name` in the trash directory over to the user of the trash,
+    /// if it was created by root on their behalf (see [`Trash::foreign_owner`])
+    pub(crate) fn hand_over(&self, name: &Path, full_path: &Path) -> crate::Result<()> {
+        match self.trash.foreign_owner() {
+            Some((uid, gid)) => {
+                dirfd::chown_at(self.dir(), name, uid, gid).fs_err(FsOp::Chown, full_path)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the `.trashinfo` file of the entry `name`, returning `false` if it doesn't exist
+    pub(crate) fn remove_info(&self, name: &OsStr) -> crate::Result<bool> {
+        let mut info_name = name.to_owned();
+        info_name.push(".trashinfo");
+        match dirfd::unlink_at(self.info(), Path::new(&info_name)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e).fs_err(FsOp::RemoveFile, self.trash.info_dir.join(info_name)),
+        }
+    }
+}
+
+fn open_dir(dir: Dir, path: &Path, follow: bool, full_path: &Path) -> crate::Result<OwnedFd> {
+    // With `O_DIRECTORY`, Linux reports symlinks as ENOTDIR instead of ELOOP
+    let is_symlink =
+        || dirfd::stat_at(dir, path).is_ok_and(|x| x.st_mode & libc::S_IFMT == libc::S_IFLNK);
+    dirfd::open_dir_at(dir, path, follow).map_err(|e| match e.raw_os_error() {
+        Some(libc::ELOOP) => crate::Error::IsSymlink(full_path.to_owned()),
+        Some(libc::ENOTDIR) if !follow && is_symlink() => {
+            crate::Error::IsSymlink(full_path.to_owned())
+        }
+        Some(libc::ENOTDIR) => crate::Error::NotADirectory(full_path.to_owned()),
+        _ => crate::Error::Fs {
+            op: FsOp::Open,
+            path: full_path.to_owned(),
+            source: e,
+        },
+    })
+}
+
+/// Opens the directory `name`, creating it first if `create` is set.
+///
+/// A directory that is created is handed over to `owner`, if set (see [`Trash::foreign_owner`]).
+pub(crate) fn open_or_create(
+    dir: Dir,
+    name: &Path,
+    create: bool,
+    owner: Option<Owner>,
+    follow: bool,
+    full_path: &Path,
+) -> crate::Result<OwnedFd> {
+    if create {
+        match dirfd::mkdir_at(dir, name, DIR_MODE) {
+            Ok(()) => {
+                if let Some((uid, gid)) = owner {
+                    dirfd::chown_at(dir, name, uid, gid).fs_err(FsOp::Chown, full_path)?;
+                }
+            }
+            Err(e) if e.kind() != io::ErrorKind::AlreadyExists => {
+                return Err(e).fs_err(FsOp::CreateDir, full_path)
+            }
+            Err(_) => {}
+        }
+    }
+    open_dir(dir, name, follow, full_path)
+}
@@ -1,26 +1,57 @@
 use super::Trash;
-use crate::trash::TrashType;
+use crate::{
+    error::{FsOp, IoResultExt},
+    trash::TrashType,
+    User,
+};
 use std::{env, fs, os::unix::fs::MetadataExt, path::PathBuf};
 
 impl Trash {
     /// Finds or creates the users home trashcan (located at  `$XDG_DATA_HOME/.local/share/Trash`)
     pub fn find_or_create_home_trash() -> crate::Result<Self> {
+        let trash = Self::find_home_trash()?;
+        trash.create_dirs()?;
+        Ok(trash)
+    }
+
+    /// Finds the users home trashcan (located at  `$XDG_DATA_HOME/.local/share/Trash`),
+    /// without creating anything on disk.
+    ///
+    /// The trash might not exist yet, in which case it is empty and will be created
+    /// on the first [`Trash::put`].
+    pub fn find_home_trash() -> crate::Result<Self> {
         let home_dir = PathBuf::from(env::var("HOME").map_err(|_| crate::Error::Homeless)?);
 
         let xdg_data_dir = env::var("XDG_DATA_HOME")
             .map(PathBuf::from)
             .unwrap_or(home_dir.join(".local").join("share"));
 
+        Self::home_trash_at(home_dir, xdg_data_dir, unsafe { libc::getuid() })
+    }
+
+    /// Finds the home trashcan of another user, located at `~/.local/share/Trash` in the home
+    /// directory from the passwd database.
+    ///
+    /// The environment of other users is unknown, so a custom `$XDG_DATA_HOME` is not respected.
+    pub fn find_home_trash_of(user: &User) -> crate::Result<Self> {
+        let xdg_data_dir = user.home_dir.join(".local").join("share");
+        Self::home_trash_at(user.home_dir.clone(), xdg_data_dir, user.uid)
+    }
+
+    fn home_trash_at(home_dir: PathBuf, xdg_data_dir: PathBuf, owner: u32) -> crate::Result<Self> {
         let trash_dir = xdg_data_dir.join("Trash");
-        fs::create_dir_all(&trash_dir)?;
-        let trash_dir_meta = fs::metadata(&xdg_data_dir)?;
+
+        // The trash will be created on the same device as the closest existing parent directory
+        let existing_parent = xdg_data_dir
+            .ancestors()
+            .find(|x| x.exists())
+            .unwrap_or(&xdg_data_dir);
+        let trash_dir_meta = fs::metadata(existing_parent).fs_err(FsOp::Stat, existing_parent)?;
 
         let info_dir = trash_dir.join("info");
         let files_dir = trash_dir.join("files");
-        fs::create_dir_all(&info_dir)?;
-        fs::create_dir_all(&files_dir)?;
 
-        log::debug!("Home trash at: {}", trash_dir.display());
+        event!(debug, "Found home trash", trash = trash_dir.display());
 
         Ok(Self {
             device: trash_dir_meta.dev(),
@@ -30,6 +61,7 @@ impl Trash {
             files_dir,
             trash_type: TrashType::Home,
             use_relative_path: false,
+            owner,
         })
     }
 }
@@ -1,13 +1,35 @@
+use crate::{
+    error::{FsOp, IoResultExt},
+    User,
+};
 use std::{
+    ffi::CString,
     fmt::{Debug, Display},
+    fs::{self, DirBuilder, ReadDir},
+    io,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{lchown, DirBuilderExt, MetadataExt},
+    },
     path::{Path, PathBuf},
 };
 
 mod admin_trash;
+mod custom_trash;
+mod dirs;
 mod home_trash;
 mod operations;
 mod user_trash;
 
+pub(crate) use dirs::{open_or_create, TrashDirs};
+pub use operations::empty::{EmptyReport, TrashTotals};
+pub use operations::permissions::PermissionIssue;
+pub(crate) use operations::{
+    empty::remove_raw_where,
+    size::{apparent_size, size_of_payloads},
+};
+pub(crate) use user_trash::user_trash_dir;
+
 /// A single trashcan on the system.
 ///
 /// ## Note about `mount_root`
@@ -25,10 +47,12 @@ pub struct Trash {
     files_dir: PathBuf,
     trash_type: TrashType,
     use_relative_path: bool,
+    owner: u32,
 }
 
 /// The type of a trashcan
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TrashType {
     /// Located in the users home directory, ususally in `~/.local/share/Trash`
     Home,
@@ -72,6 +96,18 @@ impl Trash {
         &self.info_dir
     }
 
+    /// The trash directory itself, containing the `info` and `files` directories
+    pub(crate) fn trash_dir(&self) -> &Path {
+        self.info_dir.parent().unwrap_or(&self.info_dir)
+    }
+
+    /// Creates the `info` and `files` directories if they don't exist yet.
+    ///
+    /// Discovering a trash never creates anything, so this has to be called before writing to it.
+    pub(crate) fn create_dirs(&self) -> crate::Result<()> {
+        self.open_dirs(true).map(drop)
+    }
+
     /// Directory where trashes files are stored
     #[must_use]
     pub fn files_dir(&self) -> &Path {
@@ -112,9 +148,105 @@ impl Trash {
         self.use_relative_path
     }
 
+    /// The uid of the user this trash belongs to
+    #[must_use]
+    pub fn owner(&self) -> u32 {
+        self.owner
+    }
+
+    /// The user and group that files created in this trash should be handed to, see [`foreign_owner`]
+    pub(crate) fn foreign_owner(&self) -> Option<Owner> {
+        foreign_owner(self.owner, self.trash_dir())
+    }
+
+    /// Whether the current user can put files into this trash.
+    ///
+    /// Checked on the trash directory, or on the directory it would be created in if it doesn't exist yet.
+    pub(crate) fn is_writable(&self) -> bool {
+        let trash_dir = self.trash_dir();
+        let dir = if trash_dir.exists() {
+            trash_dir
+        } else {
+            trash_dir.parent().unwrap_or(trash_dir)
+        };
+        let Ok(path) = CString::new(dir.as_os_str().as_bytes()) else {
+            return false;
+        };
+        unsafe { libc::faccessat(libc::AT_FDCWD, path.as_ptr(), libc::W_OK, libc::AT_EACCESS) == 0 }
+    }
+
     /// The device id of the filesystem this trash resides on
     #[must_use]
     pub fn device(&self) -> u64 {
         self.device
     }
+
+    /// Checks if the filesystem of this trash is still mounted at the same location,
+    /// by comparing the device of the mount root to the device the trash was found on.
+    #[must_use]
+    pub fn is_available(&self) -> bool {
+        fs::metadata(&self.mount_root).is_ok_and(|x| x.dev() == self.device)
+    }
+}
+
+/// Mode of the directories of a trash, other users must not see what was trashed
+pub(crate) const DIR_MODE: u32 = 0o700;
+
+/// Mode of `.trashinfo` files and other files inside of a trash, as they contain the original paths
+pub(crate) const FILE_MODE: u32 = 0o600;
+
+/// A user and optionally a group to hand created files over to
+pub(crate) type Owner = (u32, Option<u32>);
+
+/// The user and group that files created on behalf of the user `uid` should be handed to.
+///
+/// This is only the case when running as root for another user, as the user couldn't modify
+/// anything that root creates inside of their trash otherwise. The group is taken from the
+/// trash directory if it exists, otherwise it's the primary group of the user.
+pub(crate) fn foreign_owner(uid: u32, trash_dir: &Path) -> Option<Owner> {
+    if unsafe { libc::geteuid() } != 0 || uid == 0 {
+        return None;
+    }
+    let gid = fs::symlink_metadata(trash_dir)
+        .map(|x| x.gid())
+        .ok()
+        .or_else(|| User::from_uid(uid).ok().flatten().map(|x| x.gid));
+    Some((uid, gid))
+}
+
+/// Like [`fs::create_dir_all`], but creates directories with `mode` and hands them over to `owner`
+pub(crate) fn create_dir_all_owned(
+    path: &Path,
+    mode: u32,
+    owner: Option<Owner>,
+) -> crate::Result<()> {
+    let missing = path
+        .ancestors()
+        .take_while(|x| fs::symlink_metadata(x).is_err())
+        .map(Path::to_owned)
+        .collect::<Vec<_>>();
+    DirBuilder::new()
+        .recursive(true)
+        .mode(mode)
+        .create(path)
+        .fs_err(FsOp::CreateDir, path)?;
+
+    if let Some((uid, gid)) = owner {
+        for dir in missing.iter().rev() {
+            lchown(dir, Some(uid), gid).fs_err(FsOp::Chown, dir)?;
+        }
+    }
+    Ok(())
+}
+
+/// Like [`fs::read_dir`], but returns `None` if the directory doesn't exist.
+///
+/// Trashes are only created once something is put into them, so a missing
+/// `info` or `files` directory just means the trash is empty.
+pub(crate) fn read_dir_if_exists(dir: &Path) -> crate::Result<Option<ReadDir>> {
+    match fs::read_dir(dir) {
+        Ok(x) => Ok(Some(x)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).fs_err(FsOp::ReadDir, dir),
+    }
 }
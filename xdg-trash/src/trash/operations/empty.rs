@@ -1,32 +1,209 @@
-use crate::trash::Trash;
-use std::{fs, path::PathBuf};
+use super::size::apparent_size;
+use crate::{
+    progress::{Progress, ProgressHandler, Tracker},
+    trash::Trash,
+    trash_entry::RawEntry,
+    trash_file::TrashFile,
+};
+use std::{
+    ffi::OsStr,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// The outcome of emptying trashcans or removing files from them,
+/// see [`Trash::empty`] and [`Trash::remove_where`].
+#[derive(Debug, Default)]
+#[must_use]
+pub struct EmptyReport {
+    /// The original paths of all removed entries.
+    ///
+    /// For broken entries without a readable `.trashinfo` file, the path inside the trash is used.
+    pub removed: Vec<PathBuf>,
+    /// Entries that could not be removed, with the reason why
+    pub failed: Vec<(PathBuf, crate::Error)>,
+    /// The size in bytes of all removed entries
+    pub bytes_freed: u64,
+    /// The totals of each trash involved
+    pub trashes: Vec<TrashTotals>,
+    /// Whether the operation was cancelled through a [`ProgressHandler`].
+    /// The remaining entries were left untouched.
+    pub cancelled: bool,
+}
+
+/// The totals of a single trash in an [`EmptyReport`]
+#[derive(Debug)]
+pub struct TrashTotals {
+    /// The trash these totals belong to
+    pub trash: Rc<Trash>,
+    /// The amount of removed entries
+    pub removed: usize,
+    /// The amount of entries that could not be removed
+    pub failed: usize,
+    /// The size in bytes of all removed entries
+    pub bytes_freed: u64,
+}
 
 impl Trash {
-    /// Permanently removes all files currently in this trash
-    pub fn empty(&self) -> crate::Result<impl Iterator<Item = crate::Result<PathBuf>>> {
-        empty_inner(self)
+    /// Permanently removes all entries in this trash, including broken ones.
+    ///
+    /// Every payload is first moved into the staging directory before it's `.trashinfo` file is
+    /// removed, so an interruption never leaves half deleted entries behind,
+    /// see [`Trash::purge_expunged`]. Failures don't stop the operation, they are
+    /// collected in the returned report.
+    pub fn empty(self: Rc<Self>) -> EmptyReport {
+        self.empty_with_progress(&mut |_: &Progress| ControlFlow::Continue(()))
+    }
+
+    /// Like [`Trash::empty`], but reports the progress to `progress` after each entry.
+    /// The bytes reported are the sizes of the removed entries.
+    pub fn empty_with_progress(self: Rc<Self>, progress: &mut dyn ProgressHandler) -> EmptyReport {
+        remove_raw_where(&[self], &mut |_| true, progress)
+    }
+
+    /// Permanently removes all valid entries for which `predicate` returns `true`.
+    ///
+    /// Broken entries are never passed to `predicate` and are left untouched, see [`Trash::empty`].
+    pub fn remove_where(self: Rc<Self>, predicate: impl FnMut(&TrashFile) -> bool) -> EmptyReport {
+        self.remove_where_with_progress(predicate, &mut |_: &Progress| ControlFlow::Continue(()))
+    }
+
+    /// Like [`Trash::remove_where`], but reports the progress to `progress` after each removed entry
+    pub fn remove_where_with_progress(
+        self: Rc<Self>,
+        mut predicate: impl FnMut(&TrashFile) -> bool,
+        progress: &mut dyn ProgressHandler,
+    ) -> EmptyReport {
+        remove_raw_where(
+            &[self],
+            &mut |x| matches!(x, RawEntry::Valid(file) if predicate(file)),
+            progress,
+        )
+    }
+
+    /// Removes a single entry by name, returning it's size in bytes
+    pub(crate) fn empty_entry(&self, name: &OsStr) -> crate::Result<u64> {
+        let dirs = self.open_dirs(false)?;
+        let lock = dirs.lock()?;
+        let staged = dirs.stage(name)?;
+        if let Err(e) = dirs.remove_info(name) {
+            // The entry still exists, so it gets it's payload back
+            if let Some(Err(e)) = staged.as_ref().map(|x| dirs.unstage(x, name)) {
+                event!(
+                    error,
+                    "Failed to move staged file back",
+                    name = Path::new(name).display(),
+                    error = e
+                );
+            }
+            return Err(e);
+        }
+        drop(lock);
+
+        let Some(staged) = staged else {
+            return Ok(0);
+        };
+        let bytes = apparent_size(&self.expunged_dir().join(&staged))?;
+        dirs.delete_staged(&staged)?;
+        Ok(bytes)
     }
 }
 
-fn empty_inner(trash: &Trash) -> crate::Result<impl Iterator<Item = crate::Result<PathBuf>>> {
-    let infos = fs::read_dir(&trash.info_dir)?;
-    let files = fs::read_dir(&trash.files_dir)?;
-    Ok(infos
-        .chain(files)
-        .flat_map(|x| {
-            x.map(|entry| {
-                let path = entry.path();
-                entry.file_type().map(|x| {
-                    if x.is_dir() {
-                        fs::remove_dir_all(&path)
-                    } else {
-                        fs::remove_file(&path)
-                    }
-                    .map(|()| entry.path())
-                    .map_err(crate::Error::IoError)
-                    .map_err(|e| crate::Error::FailedToDeleteFile(path, Box::new(e)))
-                })
-            })
-        })
-        .flatten())
+/// Removes every entry in `trashes` matching `predicate`, this backs all
+/// emptying operations of [`Trash`] and [`crate::UnifiedTrash`].
+pub(crate) fn remove_raw_where(
+    trashes: &[Rc<Trash>],
+    predicate: &mut dyn FnMut(&RawEntry) -> bool,
+    progress: &mut dyn ProgressHandler,
+) -> EmptyReport {
+    let _span = span!("empty", trashes = trashes.len());
+    let mut report = EmptyReport::default();
+
+    // Everything is listed up front, so the progress has a total
+    let mut matching = Vec::new();
+    for (i, trash) in trashes.iter().enumerate() {
+        report.trashes.push(TrashTotals {
+            trash: trash.clone(),
+            removed: 0,
+            failed: 0,
+            bytes_freed: 0,
+        });
+
+        let entries = match trash.clone().list_raw() {
+            Ok(x) => x,
+            Err(e) => {
+                report.fail(i, trash.trash_dir().to_owned(), e);
+                continue;
+            }
+        };
+        for entry in entries {
+            match entry {
+                Ok(entry) if predicate(&entry) => matching.push((i, entry)),
+                Ok(_) => {}
+                Err(e) => {
+                    let path = e.path().unwrap_or(trash.trash_dir()).to_owned();
+                    report.fail(i, path, e);
+                }
+            }
+        }
+    }
+
+    let Ok(mut tracker) = Tracker::start(progress, matching.len() as u64, None) else {
+        report.cancelled = true;
+        return report;
+    };
+
+    for (i, entry) in matching {
+        let handle = entry.entry();
+        let path = match entry {
+            RawEntry::Valid(file) => file.original_path(),
+            RawEntry::MissingPayload { original_path, .. } => original_path,
+            RawEntry::MissingInfo { .. } => handle.files_filepath(),
+            RawEntry::Unparseable { .. } => handle.info_filepath(),
+        };
+
+        let bytes = match handle.trash().empty_entry(handle.name()) {
+            Ok(bytes) => {
+                event!(debug, "Removed entry", path = path.display(), bytes = bytes);
+                let totals = &mut report.trashes[i];
+                totals.removed += 1;
+                totals.bytes_freed += bytes;
+                report.bytes_freed += bytes;
+                report.removed.push(path);
+                bytes
+            }
+            Err(e) => {
+                event!(
+                    warn,
+                    "Failed to remove entry",
+                    path = path.display(),
+                    error = e
+                );
+                report.fail(i, path, e);
+                0
+            }
+        };
+
+        if tracker.item_done(bytes).is_err() {
+            report.cancelled = true;
+            return report;
+        }
+    }
+
+    // Leftovers of earlier interrupted removals
+    for (i, trash) in trashes.iter().enumerate() {
+        if let Err(e) = trash.purge_expunged() {
+            report.fail(i, trash.expunged_dir(), e);
+        }
+    }
+
+    report
+}
+
+impl EmptyReport {
+    fn fail(&mut self, trash: usize, path: PathBuf, e: crate::Error) {
+        self.trashes[trash].failed += 1;
+        self.failed.push((path, e));
+    }
 }
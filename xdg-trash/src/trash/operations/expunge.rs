@@ -0,0 +1,154 @@
+use crate::{
+    dirfd::{self, Dir},
+    error::{FsOp, IoResultExt},
+    rename::rename_noreplace,
+    trash::{open_or_create, Trash, TrashDirs},
+    ErrorKind,
+};
+use std::{
+    ffi::{OsStr, OsString},
+    io,
+    os::fd::{AsFd, OwnedFd},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const EXPUNGED_DIR: &str = "expunged";
+
+static STAGED: AtomicU64 = AtomicU64::new(0);
+
+impl Trash {
+    /// Directory where files are moved to before they are permanently deleted
+    pub(crate) fn expunged_dir(&self) -> PathBuf {
+        self.trash_dir().join(EXPUNGED_DIR)
+    }
+
+    /// Permanently deletes everything left in the staging directory of this trash.
+    ///
+    /// Removing files first moves them into a staging directory inside the trash, and only then
+    /// deletes them. If that deletion is interrupted, the leftovers are deleted by the next removal
+    /// from this trash or by calling this function.
+    ///
+    /// Returns the amount of deleted items. Deletion continues after failures,
+    /// the first error is returned once all items were tried.
+    pub fn purge_expunged(&self) -> crate::Result<usize> {
+        match self.open_dirs(false) {
+            Ok(dirs) => dirs.purge(),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl TrashDirs<'_> {
+    /// Opens the staging directory, `None` if it doesn't exist and `create` is not set
+    pub(crate) fn expunged(&self, create: bool) -> crate::Result<Option<OwnedFd>> {
+        let path = self.trash().expunged_dir();
+        let owner = self.trash().foreign_owner();
+        match open_or_create(
+            self.dir(),
+            Path::new(EXPUNGED_DIR),
+            create,
+            owner,
+            false,
+            &path,
+        ) {
+            Ok(fd) => Ok(Some(fd)),
+            Err(e) if !create && e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Atomically moves the payload of the entry `name` into the staging directory.
+    ///
+    /// Returns the name in the staging directory, or `None` if the payload doesn't exist.
+    pub(crate) fn stage(&self, name: &OsStr) -> crate::Result<Option<OsString>> {
+        let expunged = self.expunged(true)?.expect("staging directory is created");
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |x| x.as_nanos());
+        let staged = OsString::from(format!(
+            "{}-{}-{}",
+            process::id(),
+            nanos,
+            STAGED.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        match dirfd::rename_at(
+            self.files(),
+            Path::new(name),
+            Dir::Fd(expunged.as_fd()),
+            Path::new(&staged),
+        ) {
+            Ok(()) => Ok(Some(staged)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).rename_err(
+                self.trash().files_dir().join(name),
+                self.trash().expunged_dir().join(&staged),
+            ),
+        }
+    }
+
+    /// Moves the staged payload `staged` back to `name` in the files directory, undoing [`TrashDirs::stage`]
+    pub(crate) fn unstage(&self, staged: &OsStr, name: &OsStr) -> crate::Result<()> {
+        let from = self.trash().expunged_dir().join(staged);
+        let to = self.trash().files_dir().join(name);
+        let Some(expunged) = self.expunged(false)? else {
+            return Err(io::Error::from(io::ErrorKind::NotFound)).rename_err(from, to);
+        };
+        rename_noreplace(
+            Dir::Fd(expunged.as_fd()),
+            Path::new(staged),
+            self.files(),
+            Path::new(name),
+        )
+        .rename_err(from, to)
+    }
+
+    /// Deletes everything in the staging directory, see [`Trash::purge_expunged`]
+    pub(crate) fn purge(&self) -> crate::Result<usize> {
+        let Some(expunged) = self.expunged(false)? else {
+            return Ok(0);
+        };
+        let path = self.trash().expunged_dir();
+        let staged = dirfd::list_dir(expunged.as_fd()).fs_err(FsOp::ReadDir, &path)?;
+
+        let mut total = 0;
+        let mut first_err = None;
+        for name in staged {
+            match self.delete_staged(&name) {
+                Ok(()) => total += 1,
+                Err(e) => {
+                    event!(
+                        warn,
+                        "Failed to delete staged item",
+                        name = Path::new(&name).display(),
+                        error = e
+                    );
+                    first_err.get_or_insert(e);
+                }
+            }
+        }
+
+        match first_err {
+            Some(e) => Err(e),
+            None => Ok(total),
+        }
+    }
+
+    /// Deletes a staged item. Staged items can be deleted by multiple processes at once
+    /// (see [`Trash::purge_expunged`]), so it being gone already is not an error.
+    pub(crate) fn delete_staged(&self, name: &OsStr) -> crate::Result<()> {
+        let path = self.trash().expunged_dir().join(name);
+        let Some(expunged) = self.expunged(false)? else {
+            return Ok(());
+        };
+        match dirfd::remove_tree_at(expunged.as_fd(), Path::new(name)) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            other => other.fs_err(FsOp::RemoveDir, path),
+        }
+    }
+}
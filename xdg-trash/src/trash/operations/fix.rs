@@ -1,24 +1,94 @@
-use crate::{Trash, TrashFile};
-use std::{fs, rc::Rc};
+use crate::{
+    dirfd::unlink_at,
+    error::{FsOp, IoResultExt},
+    progress::{Progress, ProgressHandler, Tracker},
+    trash::read_dir_if_exists,
+    Trash, TrashFile,
+};
+use std::{
+    fs,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+
+/// Info files younger than this are never removed, as they might belong to an in-flight put
+const GRACE_PERIOD: Duration = Duration::from_secs(60);
 
 impl Trash {
-    /// Removes broken trashinfo files.
+    /// Removes broken trashinfo files and finishes interrupted removals
+    /// (see [`Trash::purge_expunged`]).
+    ///
+    /// Info files modified in the last minute are left alone, even if they look broken.
+    /// Implementations create the info file before moving the actual file into the trash,
+    /// so these might belong to a put that is still in progress.
     ///
-    /// Returns the amount of removed files.
+    /// Returns the amount of removed trashinfo files.
     pub fn fix(self: Rc<Self>) -> crate::Result<usize> {
-        let info_files = fs::read_dir(&self.info_dir)?;
+        self.fix_with_progress(&mut |_: &Progress| ControlFlow::Continue(()))
+    }
 
+    /// Like [`Trash::fix`], but reports the progress to `progress` after each checked trashinfo file
+    pub fn fix_with_progress(
+        self: Rc<Self>,
+        progress: &mut dyn ProgressHandler,
+    ) -> crate::Result<usize> {
+        let _span = span!("fix", trash = self.trash_dir().display());
+        self.purge_expunged()?;
+        let info_files = self.info_files()?;
+
+        let mut tracker = Tracker::start(progress, info_files.len() as u64, None)?;
         let mut total = 0;
-        for tfile in info_files {
-            let tfile = tfile?;
-            let tfile = tfile.path();
-            if TrashFile::from_trashinfo_path(&tfile, self.clone()).is_err() {
-                log::info!("Removing: {}", tfile.display());
-                fs::remove_file(&tfile)?;
+        for info_file in &info_files {
+            if self.clone().fix_info_file(info_file)? {
                 total += 1;
             }
+            tracker.item_done(0)?;
         }
 
         Ok(total)
     }
+
+    /// All files in the info directory
+    pub(crate) fn info_files(&self) -> crate::Result<Vec<PathBuf>> {
+        read_dir_if_exists(&self.info_dir)?
+            .into_iter()
+            .flatten()
+            .map(|x| x.map(|x| x.path()).fs_err(FsOp::ReadDir, &self.info_dir))
+            .collect()
+    }
+
+    /// Removes the trashinfo file at `path` if it's broken, returning whether it was removed
+    pub(crate) fn fix_info_file(self: Rc<Self>, path: &Path) -> crate::Result<bool> {
+        let dirs = self.open_dirs(false)?;
+        let _lock = dirs.lock()?;
+        if TrashFile::from_trashinfo_path(path, self.clone()).is_ok() {
+            return Ok(false);
+        }
+
+        let modified = fs::symlink_metadata(path)
+            .and_then(|x| x.modified())
+            .fs_err(FsOp::Stat, path)?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        if age < GRACE_PERIOD {
+            event!(
+                debug,
+                "Not removing recently modified trashinfo file",
+                info_path = path.display()
+            );
+            return Ok(false);
+        }
+
+        event!(
+            info,
+            "Removing broken trashinfo file",
+            info_path = path.display()
+        );
+        let name = path.file_name().expect("info files have a name");
+        unlink_at(dirs.info(), Path::new(name)).fs_err(FsOp::RemoveFile, path)?;
+        Ok(true)
+    }
 }
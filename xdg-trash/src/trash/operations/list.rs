@@ -1,18 +1,125 @@
-use crate::{trash::Trash, trash_file::TrashFile};
-use std::{fs, rc::Rc};
+use crate::{
+    error::{FsOp, IoResultExt},
+    trash::{read_dir_if_exists, Trash},
+    trash_entry::{RawEntry, TrashEntry},
+    trash_file::TrashFile,
+    trashinfo::TrashInfo,
+};
+use std::{ffi::OsStr, fs, path::Path, rc::Rc};
 
 impl Trash {
     /// Returns an iterator over all trashed files in this trashcan
     pub fn list(self: Rc<Self>) -> crate::Result<impl Iterator<Item = crate::Result<TrashFile>>> {
-        let info_files = fs::read_dir(&self.info_dir)?;
+        let span = span!("list", trash = self.trash_dir().display());
+        let entries = self.entries()?;
+        let span = span.exit();
+        Ok(entries.map(move |entry| span.in_scope(|| entry?.load())))
+    }
+
+    /// Returns an iterator over all entries in this trashcan, without reading any of them.
+    ///
+    /// This only reads the info directory, use [`TrashEntry::load`] to get the actual [`TrashFile`].
+    /// Files in the info directory without a `.trashinfo` extension yield an error.
+    pub fn entries(
+        self: Rc<Self>,
+    ) -> crate::Result<impl Iterator<Item = crate::Result<TrashEntry>>> {
+        let info_files = read_dir_if_exists(&self.info_dir)?;
 
-        Ok(Box::new(info_files.map(
-            move |info_file| -> crate::Result<TrashFile> {
-                let info_file = info_file?;
+        Ok(info_files
+            .into_iter()
+            .flatten()
+            .map(move |info_file| -> crate::Result<TrashEntry> {
+                let info_file = info_file.fs_err(FsOp::ReadDir, &self.info_dir)?;
                 let info_file_path = info_file.path();
 
-                TrashFile::from_trashinfo_path(&info_file_path, self.clone())
-            },
-        )))
+                if info_file_path.extension() != Some(OsStr::new("trashinfo")) {
+                    return Err(invalid(&info_file_path, crate::Error::InvalidTrashinfoExt));
+                }
+
+                let without_trashinfo_ext = info_file_path
+                    .file_stem()
+                    .ok_or_else(|| {
+                        invalid(
+                            &info_file_path,
+                            crate::Error::HasNoFileStem(info_file_path.clone()),
+                        )
+                    })?
+                    .to_owned();
+
+                Ok(TrashEntry::new(self.clone(), without_trashinfo_ext))
+            }))
+    }
+
+    /// Returns an iterator over every entry in this trashcan, including broken ones.
+    ///
+    /// First all `.trashinfo` files are yielded, followed by all files
+    /// in the files directory that have no `.trashinfo` file.
+    /// Errors are only yielded for io errors and unexpected files in the info directory.
+    pub fn list_raw(
+        self: Rc<Self>,
+    ) -> crate::Result<impl Iterator<Item = crate::Result<RawEntry>>> {
+        let span = span!("list_raw", trash = self.trash_dir().display());
+        let payloads = read_dir_if_exists(&self.files_dir)?;
+        let entries = self.clone().entries()?;
+        let span = span.exit();
+        let trash = self;
+        let payload_span = span.clone();
+
+        let missing_info = payloads
+            .into_iter()
+            .flatten()
+            .map(move |payload| {
+                payload_span.in_scope(|| -> crate::Result<Option<RawEntry>> {
+                    let payload = payload.fs_err(FsOp::ReadDir, &trash.files_dir)?;
+                    let entry = TrashEntry::new(trash.clone(), payload.file_name());
+                    if fs::symlink_metadata(entry.info_filepath()).is_ok() {
+                        Ok(None)
+                    } else {
+                        Ok(Some(RawEntry::MissingInfo { entry }))
+                    }
+                })
+            })
+            .filter_map(Result::transpose);
+
+        Ok(entries
+            .map(move |entry| span.in_scope(|| load_raw(entry?)))
+            .chain(missing_info))
     }
 }
+
+fn load_raw(entry: TrashEntry) -> crate::Result<RawEntry> {
+    let info_path = entry.info_filepath();
+    let raw = fs::read(&info_path).fs_err(FsOp::Read, &info_path)?;
+
+    let trashinfo = std::str::from_utf8(&raw)
+        .map_err(crate::Error::InvalidUtf8)
+        .and_then(|x| x.parse::<TrashInfo>());
+    let trashinfo = match trashinfo {
+        Ok(x) => x,
+        Err(error) => {
+            return Ok(RawEntry::Unparseable {
+                entry,
+                raw,
+                error: invalid(&info_path, error),
+            })
+        }
+    };
+
+    if !entry.has_payload() {
+        return Ok(RawEntry::MissingPayload {
+            original_path: trashinfo.original_path(entry.trash()),
+            deleted_at: trashinfo.deleted_at(),
+            entry,
+        });
+    }
+
+    Ok(RawEntry::Valid(TrashFile::new_unchecked(
+        entry.trash_rc(),
+        trashinfo,
+        entry.name().to_owned(),
+    )))
+}
+
+fn invalid(info_file_path: &Path, e: crate::Error) -> crate::Error {
+    crate::Error::InvalidTrashinfoFile(info_file_path.to_owned(), Box::new(e))
+}
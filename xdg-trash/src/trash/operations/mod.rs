@@ -1,4 +1,7 @@
 pub mod empty;
+pub mod expunge;
 pub mod fix;
 pub mod list;
+pub mod permissions;
 pub mod put;
+pub mod size;
@@ -0,0 +1,131 @@
+use crate::{
+    dirfd::{self, Dir},
+    error::{FsOp, IoResultExt},
+    trash::Trash,
+    ErrorKind,
+};
+use std::{
+    io,
+    os::fd::{AsFd, BorrowedFd},
+    path::{Path, PathBuf},
+};
+
+/// Permission bits of the group and other users
+const SHARED_BITS: u32 = 0o077;
+
+/// A file or directory of a trash that other users can access, see [`Trash::check_permissions`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionIssue {
+    /// The file or directory
+    pub path: PathBuf,
+    /// The permission bits it had when it was checked
+    pub mode: u32,
+    /// The permission bits it should have, which are `mode` without any access for others
+    pub expected: u32,
+    /// Whether the permissions were changed to `expected`
+    pub fixed: bool,
+}
+
+impl Trash {
+    /// Checks if other users can access this trash, which would allow them to see the
+    /// original paths and contents of everything trashed.
+    ///
+    /// The trash directory, it's `info`, `files` and staging directories and all files directly
+    /// inside of the trash and `info` directories are checked. Trashes created by this crate are
+    /// private to their user (directories have mode `0700` and files `0600`), but trashes created
+    /// by other implementations might not be. If `fix` is set, the group and other permission bits
+    /// are removed from everything reported.
+    ///
+    /// A trash that doesn't exist yet has no issues.
+    pub fn check_permissions(&self, fix: bool) -> crate::Result<Vec<PermissionIssue>> {
+        let _span = span!(
+            "check_permissions",
+            trash = self.trash_dir().display(),
+            fix = fix
+        );
+        let dirs = match self.open_dirs(false) {
+            Ok(x) => x,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let expunged = dirs.expunged(false)?;
+
+        let mut issues = Vec::new();
+        let mut dirs_to_check = vec![
+            (dirs.dir.as_fd(), self.trash_dir().to_owned()),
+            (dirs.info.as_fd(), self.info_dir.clone()),
+            (dirs.files.as_fd(), self.files_dir.clone()),
+        ];
+        if let Some(expunged) = &expunged {
+            dirs_to_check.push((expunged.as_fd(), self.expunged_dir()));
+        }
+        for (fd, path) in dirs_to_check {
+            issues.extend(check(fd, path, fix)?);
+        }
+
+        for (fd, path) in [
+            (dirs.dir.as_fd(), self.trash_dir()),
+            (dirs.info.as_fd(), self.info_dir()),
+        ] {
+            check_files(fd, path, fix, &mut issues)?;
+        }
+
+        for issue in &issues {
+            event!(
+                debug,
+                "Trash is accessible by other users",
+                path = issue.path.display(),
+                mode = format_args!("{:o}", issue.mode),
+                fixed = issue.fixed
+            );
+        }
+        Ok(issues)
+    }
+}
+
+/// Checks all regular files directly inside of `dir`, symlinks and special files are skipped
+fn check_files(
+    dir: BorrowedFd,
+    path: &Path,
+    fix: bool,
+    issues: &mut Vec<PermissionIssue>,
+) -> crate::Result<()> {
+    for name in dirfd::list_dir(dir).fs_err(FsOp::ReadDir, path)? {
+        let name = Path::new(&name);
+        let full_path = path.join(name);
+        let is_file = dirfd::stat_at(Dir::Fd(dir), name)
+            .is_ok_and(|x| x.st_mode & libc::S_IFMT == libc::S_IFREG);
+        if !is_file {
+            continue;
+        }
+
+        // Non-blocking, so a fifo swapped in after the check can't hang the open
+        let file =
+            match dirfd::open_file_at(Dir::Fd(dir), name, libc::O_RDONLY | libc::O_NONBLOCK, 0) {
+                Ok(x) => x,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e).fs_err(FsOp::Open, &full_path),
+            };
+        issues.extend(check(file.as_fd(), full_path, fix)?);
+    }
+    Ok(())
+}
+
+fn check(fd: BorrowedFd, path: PathBuf, fix: bool) -> crate::Result<Option<PermissionIssue>> {
+    let stat = dirfd::fstat(fd).fs_err(FsOp::Stat, &path)?;
+    let mode = stat.st_mode & 0o7777;
+    if mode & SHARED_BITS == 0 {
+        return Ok(None);
+    }
+
+    let expected = mode & !SHARED_BITS;
+    if fix {
+        dirfd::fchmod(fd, expected).fs_err(FsOp::Chmod, &path)?;
+    }
+    Ok(Some(PermissionIssue {
+        path,
+        mode,
+        expected,
+        fixed: fix,
+    }))
+}
@@ -1,9 +1,18 @@
-use crate::{lexical_absolute, trash::Trash, trash_file::TrashFile, trashinfo::TrashInfo};
-use chrono::Local;
+use crate::{
+    dirfd::{open_file_at, unlink_at, Dir},
+    error::{FsOp, IoResultExt},
+    index::{Change, Journal},
+    macros::OpSpan,
+    physical_absolute, physical_or_given,
+    rename::rename_noreplace,
+    trash::{Trash, FILE_MODE},
+    trash_file::TrashFile,
+    trashinfo::TrashInfo,
+};
 use std::{
     borrow::Cow,
-    fs::{self, File},
-    os::unix::fs::MetadataExt,
+    fs,
+    os::unix::fs::{fchown, MetadataExt},
     path::Path,
     rc::Rc,
 };
@@ -11,22 +20,64 @@ use std::{
 impl Trash {
     /// Puts the file at `input_path` into this trashcan, returning the created trashfile
     pub fn put(self: Rc<Self>, input_path: &Path) -> crate::Result<TrashFile> {
-        put_inner(self, input_path)
+        self.put_with_extra(input_path, &[])
+    }
+
+    /// Like [`Trash::put`], but also writes the extension keys in `extra` into the `.trashinfo` file.
+    ///
+    /// Keys must start with `X-`, see [`TrashInfo`].
+    pub fn put_with_extra(
+        self: Rc<Self>,
+        input_path: &Path,
+        extra: &[(&str, &str)],
+    ) -> crate::Result<TrashFile> {
+        let span = span!(
+            "put",
+            trash = self.trash_dir().display(),
+            input = input_path.display();
+            name
+        );
+        put_inner(self, input_path, extra, &span)
             .map_err(|e| crate::Error::FailedToTrashFile(input_path.to_owned(), Box::new(e)))
     }
 }
 
-fn put_inner(trash: Rc<Trash>, input_path: &Path) -> crate::Result<TrashFile> {
-    let input_path = lexical_absolute(input_path)?;
-    let input_path_meta = fs::symlink_metadata(&input_path)?;
+fn put_inner(
+    trash: Rc<Trash>,
+    input_path: &Path,
+    extra: &[(&str, &str)],
+    span: &OpSpan,
+) -> crate::Result<TrashFile> {
+    let input_path = physical_absolute(input_path)?;
+    let input_path_meta = fs::symlink_metadata(&input_path).fs_err(FsOp::Stat, &input_path)?;
     if input_path_meta.dev() != trash.device {
         return Err(crate::Error::DifferentDevice);
     }
 
+    // Built up front, so nothing is written if the path or an extension key is invalid
+    let info_path = if trash.use_relative_path {
+        input_path
+            .strip_prefix(physical_or_given(&trash.mount_root))
+            .map_err(|_| crate::Error::InputNotChildOfTrashMount)?
+            .to_owned()
+    } else {
+        input_path.clone()
+    };
+    let trashinfo = extra
+        .iter()
+        .fold(TrashInfo::builder(info_path), |builder, (key, value)| {
+            builder.extra(*key, *value)
+        })
+        .build()?;
+
     let trash_name = input_path.file_name().ok_or(crate::Error::HasNoFilename)?;
+    let owner = trash.foreign_owner();
+    let dirs = trash.open_dirs(true)?;
+    let lock = dirs.lock()?;
+    let journal = Journal::begin(&dirs);
 
     let mut iter: u64 = 0;
-    let (trashinfo, trash_name) = loop {
+    let trash_name = loop {
         iter += 1;
         let trash_name = if iter == 1 {
             Cow::Borrowed(trash_name)
@@ -56,7 +107,6 @@ fn put_inner(trash: Rc<Trash>, input_path: &Path) -> crate::Result<TrashFile> {
 
             // If we can't build an extension preserving name, we just append the iteration number.
             Cow::Owned(ext_preverving_name.unwrap_or_else(|| {
-                log::trace!("name without ext");
                 let mut new = trash_name.to_owned();
                 new.push("_");
                 new.push(iter.to_string());
@@ -64,61 +114,87 @@ fn put_inner(trash: Rc<Trash>, input_path: &Path) -> crate::Result<TrashFile> {
             }))
         };
 
-        log::trace!("Got name {:?}", trash_name);
+        event!(
+            trace,
+            "Trying name",
+            name = Path::new(&trash_name).display()
+        );
 
         let mut trash_name_info = trash_name.clone().into_owned();
         trash_name_info.push(".trashinfo");
-        let full_trash_path_info = trash.info_dir.join(trash_name_info);
-        let trashinfo = {
-            let trashinfo_file = match File::options()
-                .create_new(true)
-                .write(true)
-                .truncate(true)
-                .open(&full_trash_path_info)
-            {
+        let full_trash_path_info = trash.info_dir.join(&trash_name_info);
+        {
+            let trashinfo_file = match open_file_at(
+                dirs.info(),
+                Path::new(&trash_name_info),
+                libc::O_CREAT | libc::O_EXCL | libc::O_WRONLY,
+                FILE_MODE,
+            ) {
                 Ok(v) => v,
                 Err(e) => match e.kind() {
                     std::io::ErrorKind::AlreadyExists => {
-                        log::trace!("Name not unique, trying next..");
+                        event!(
+                            trace,
+                            "Name taken in info directory, trying next",
+                            name = Path::new(&trash_name).display()
+                        );
                         continue;
                     }
 
                     _ => {
-                        return Err(crate::Error::IoError(e));
+                        return Err(e).fs_err(FsOp::Create, &full_trash_path_info);
                     }
                 },
             };
 
-            let trashinfo = TrashInfo {
-                path: if trash.use_relative_path {
-                    input_path
-                        .strip_prefix(&trash.mount_root)
-                        .map_err(|_| crate::Error::InputNotChildOfTrashMount)
-                        .map(ToOwned::to_owned)?
-                } else {
-                    input_path.clone()
-                },
-                deleted_at: Local::now().naive_local(),
-            };
-            trashinfo.write_to(trashinfo_file)?;
+            if let Some((uid, gid)) = owner {
+                fchown(&trashinfo_file, Some(uid), gid)
+                    .fs_err(FsOp::Chown, &full_trash_path_info)?;
+            }
             trashinfo
-        };
+                .write_to(trashinfo_file)
+                .fs_err(FsOp::Write, &full_trash_path_info)?;
+        }
 
+        // Never overwrite a payload without a trashinfo file, it might belong to another put
         let full_trash_path_files = trash.files_dir.join(&trash_name);
-        if let Err(e) = fs::rename(&input_path, full_trash_path_files) {
-            log::error!("Failed to move file into trash, reverting trashinfo file");
-            if fs::remove_file(full_trash_path_info).is_err() {
-                log::error!("Failed to revert trashinfo file");
+        if let Err(e) =
+            rename_noreplace(Dir::Cwd, &input_path, dirs.files(), Path::new(&trash_name))
+        {
+            let taken = e.kind() == std::io::ErrorKind::AlreadyExists;
+            if !taken {
+                event!(
+                    error,
+                    "Failed to move file into trash, reverting trashinfo file",
+                    error = e
+                );
             }
-            return Err(crate::Error::FailedToMoveFile(e));
+            if unlink_at(dirs.info(), Path::new(&trash_name_info)).is_err() {
+                event!(
+                    error,
+                    "Failed to revert trashinfo file",
+                    info_path = full_trash_path_info.display()
+                );
+            }
+            if taken {
+                event!(
+                    trace,
+                    "Name taken in files directory, trying next",
+                    name = Path::new(&trash_name).display()
+                );
+                continue;
+            }
+            return Err(e).rename_err(&input_path, &full_trash_path_files);
         };
 
-        break (trashinfo, trash_name);
+        break trash_name;
     };
+    span.record("name", Path::new(&trash_name).display());
 
-    Ok(TrashFile::new_unchecked(
-        trash,
-        trashinfo,
-        trash_name.into_owned(),
-    ))
+    let trash_file = TrashFile::new_unchecked(trash.clone(), trashinfo, trash_name.into_owned());
+    if let Some(journal) = journal {
+        journal.commit(&dirs, &[Change::Added(&trash_file)]);
+    }
+    drop(lock);
+    Ok(trash_file)
 }
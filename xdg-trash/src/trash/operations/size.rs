@@ -0,0 +1,53 @@
+use crate::{
+    error::{FsOp, IoResultExt},
+    progress::{ProgressHandler, Tracker},
+    trash::{read_dir_if_exists, Trash},
+};
+use std::{fs, path::Path, path::PathBuf};
+
+impl Trash {
+    /// Calculates the size in bytes of all files in this trash.
+    ///
+    /// Progress is reported after each top level entry, see [`ProgressHandler`].
+    pub fn size_with_progress(&self, progress: &mut dyn ProgressHandler) -> crate::Result<u64> {
+        size_of_payloads(&self.payloads()?, progress)
+    }
+
+    /// All entries in the files directory
+    pub(crate) fn payloads(&self) -> crate::Result<Vec<PathBuf>> {
+        read_dir_if_exists(&self.files_dir)?
+            .into_iter()
+            .flatten()
+            .map(|x| x.map(|x| x.path()).fs_err(FsOp::ReadDir, &self.files_dir))
+            .collect()
+    }
+}
+
+/// Sums up the sizes of `payloads`, reporting the progress after each one
+pub(crate) fn size_of_payloads(
+    payloads: &[PathBuf],
+    progress: &mut dyn ProgressHandler,
+) -> crate::Result<u64> {
+    let mut tracker = Tracker::start(progress, payloads.len() as u64, None)?;
+    for payload in payloads {
+        tracker.item_done(apparent_size(payload)?)?;
+    }
+    Ok(tracker.progress().bytes_done)
+}
+
+/// The apparent size in bytes of `path` and everything below it, without following symlinks.
+///
+/// This is the sum of the file lengths, not the space they take up on disk.
+pub(crate) fn apparent_size(path: &Path) -> crate::Result<u64> {
+    let meta = fs::symlink_metadata(path).fs_err(FsOp::Stat, path)?;
+    if !meta.is_dir() {
+        return Ok(meta.len());
+    }
+
+    let mut total = meta.len();
+    for entry in fs::read_dir(path).fs_err(FsOp::ReadDir, path)? {
+        let entry = entry.fs_err(FsOp::ReadDir, path)?;
+        total += apparent_size(&entry.path())?;
+    }
+    Ok(total)
+}
@@ -1,5 +1,8 @@
 use super::Trash;
-use crate::trash::TrashType;
+use crate::{
+    error::{FsOp, IoResultExt},
+    trash::{create_dir_all_owned, foreign_owner, TrashType, DIR_MODE},
+};
 use std::{
     fs,
     os::unix::fs::MetadataExt,
@@ -7,32 +10,45 @@ use std::{
 };
 
 impl Trash {
-    /// Find a user-created trashcan (`.Trash-{uid}`) at the given mount root
+    /// Find a user-created trashcan (`.Trash-{uid}`) at the given mount root.
+    ///
+    /// This never creates anything on disk. Missing `info` and `files` directories
+    /// are created on the first [`Trash::put`].
     pub fn find_user_trash(mount_root: PathBuf) -> crate::Result<Self> {
-        Self::user_trash_inner(mount_root, false)
+        Self::find_user_trash_of(mount_root, unsafe { libc::getuid() })
+    }
+
+    /// Like [`Trash::find_user_trash`], but for the user with the id `uid` (`.Trash-{uid}`)
+    pub fn find_user_trash_of(mount_root: PathBuf, uid: u32) -> crate::Result<Self> {
+        Self::user_trash_inner(mount_root, uid, false)
     }
 
     /// Create a user-created trashcan (`.Trash-{uid}`) at the given mount root
     pub fn create_user_trash(mount_root: PathBuf) -> crate::Result<Self> {
-        Self::user_trash_inner(mount_root, true)
+        Self::create_user_trash_of(mount_root, unsafe { libc::getuid() })
+    }
+
+    /// Like [`Trash::create_user_trash`], but for the user with the id `uid`.
+    ///
+    /// When running as root, the created directory is handed over to the user.
+    pub fn create_user_trash_of(mount_root: PathBuf, uid: u32) -> crate::Result<Self> {
+        Self::user_trash_inner(mount_root, uid, true)
     }
 
-    fn user_trash_inner(mount_root: PathBuf, create: bool) -> crate::Result<Self> {
-        let trash_dir = get_trash_dir(&mount_root);
+    fn user_trash_inner(mount_root: PathBuf, uid: u32, create: bool) -> crate::Result<Self> {
+        let trash_dir = user_trash_dir(&mount_root, uid);
         if create {
-            fs::create_dir_all(&trash_dir)?;
+            create_dir_all_owned(&trash_dir, DIR_MODE, foreign_owner(uid, &trash_dir))?;
         }
-        let trash_dir_meta = fs::metadata(&trash_dir)?;
+        let trash_dir_meta = fs::metadata(&trash_dir).fs_err(FsOp::Stat, &trash_dir)?;
 
         let info_dir = trash_dir.join("info");
         let files_dir = trash_dir.join("files");
-        fs::create_dir_all(&info_dir)?;
-        fs::create_dir_all(&files_dir)?;
 
         if create {
-            log::info!("Created user trash at: {}", trash_dir.display());
+            event!(info, "Created user trash", trash = trash_dir.display());
         } else {
-            log::debug!("Found user trash at: {}", trash_dir.display());
+            event!(debug, "Found user trash", trash = trash_dir.display());
         }
 
         Ok(Self {
@@ -43,12 +59,12 @@ impl Trash {
             files_dir,
             trash_type: TrashType::User,
             use_relative_path: true,
+            owner: uid,
         })
     }
 }
 
-fn get_trash_dir(mount_root: &Path) -> PathBuf {
-    let uid = unsafe { libc::getuid() };
+pub(crate) fn user_trash_dir(mount_root: &Path, uid: u32) -> PathBuf {
     let mut trash_dir = ".Trash-".to_owned();
     trash_dir.push_str(&uid.to_string());
     mount_root.join(trash_dir)
@@ -0,0 +1,129 @@
+use crate::{trash::Trash, trash_file::TrashFile};
+use chrono::NaiveDateTime;
+use std::{
+    ffi::{OsStr, OsString},
+    fs,
+    path::PathBuf,
+    rc::Rc,
+};
+
+/// A lightweight handle to an entry in a trash.
+///
+/// Unlike [`TrashFile`], creating an entry doesn't touch the filesystem at all,
+/// it only consists of the entries name and the trash it's in. The `.trashinfo` file is
+/// only read once it's actually needed (see [`Self::load`]), so counting or
+/// filtering entries by name only costs reading the info directory.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrashEntry {
+    trash: Rc<Trash>,
+    /// Filename WITHOUT .trashinfo ext
+    raw_filename: OsString,
+}
+
+impl TrashEntry {
+    pub(crate) fn new(trash: Rc<Trash>, raw_filename: OsString) -> Self {
+        Self {
+            trash,
+            raw_filename,
+        }
+    }
+
+    /// The name of this entry in the files directory
+    #[must_use]
+    pub fn name(&self) -> &OsStr {
+        &self.raw_filename
+    }
+
+    /// Returns a reference to the trash this entry is in
+    #[must_use]
+    pub fn trash(&self) -> &Trash {
+        &self.trash
+    }
+
+    pub(crate) fn trash_rc(&self) -> Rc<Trash> {
+        self.trash.clone()
+    }
+
+    /// Full path to this entrys entry in the files directory
+    #[must_use]
+    pub fn files_filepath(&self) -> PathBuf {
+        self.trash.files_dir().join(&self.raw_filename)
+    }
+
+    /// Full path to this trash entrys .trashinfo file
+    #[must_use]
+    pub fn info_filepath(&self) -> PathBuf {
+        let mut base_filename = self.raw_filename.clone();
+        base_filename.push(".trashinfo");
+        self.trash.info_dir().join(base_filename)
+    }
+
+    /// Checks if the actual file for this entry exists in the files directory
+    #[must_use]
+    pub fn has_payload(&self) -> bool {
+        fs::symlink_metadata(self.files_filepath()).is_ok()
+    }
+
+    /// Reads and parses the `.trashinfo` file of this entry and checks that the actual file exists.
+    ///
+    /// # Errors
+    /// Same as [`TrashFile::from_trashinfo_path`]
+    pub fn load(&self) -> crate::Result<TrashFile> {
+        TrashFile::from_trashinfo_path(&self.info_filepath(), self.trash.clone())
+    }
+}
+
+/// A single entry of a trash as it is on disk, including broken entries.
+///
+/// Returned by [`Trash::list_raw`], which unlike [`Trash::list`] doesn't hide
+/// broken entries behind errors, so they can be shown and repaired.
+#[derive(Debug)]
+pub enum RawEntry {
+    /// A valid entry with both a `.trashinfo` file and the actual file
+    Valid(TrashFile),
+
+    /// A valid `.trashinfo` file without a corresponding file in the files directory
+    MissingPayload {
+        /// The entry, whose [`TrashEntry::info_filepath`] exists
+        entry: TrashEntry,
+        /// Where the file was originally located
+        original_path: PathBuf,
+        /// When the file was trashed
+        deleted_at: NaiveDateTime,
+    },
+
+    /// A file in the files directory without a corresponding `.trashinfo` file
+    MissingInfo {
+        /// The entry, whose [`TrashEntry::files_filepath`] exists
+        entry: TrashEntry,
+    },
+
+    /// A `.trashinfo` file that could not be parsed
+    Unparseable {
+        /// The entry, whose [`TrashEntry::info_filepath`] exists
+        entry: TrashEntry,
+        /// The raw contents of the `.trashinfo` file
+        raw: Vec<u8>,
+        /// Why parsing failed
+        error: crate::Error,
+    },
+}
+
+impl RawEntry {
+    /// Returns the entry, regardless of whether it is valid
+    #[must_use]
+    pub fn entry(&self) -> TrashEntry {
+        match self {
+            Self::Valid(file) => TrashEntry::new(file.trash_rc(), file.raw_filename().to_owned()),
+            Self::MissingPayload { entry, .. }
+            | Self::MissingInfo { entry }
+            | Self::Unparseable { entry, .. } => entry.clone(),
+        }
+    }
+
+    /// Returns `true` if this is a valid entry
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Self::Valid(_))
+    }
+}
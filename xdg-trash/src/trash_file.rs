@@ -1,11 +1,24 @@
-use crate::{trash::Trash, trashinfo::TrashInfo};
+use crate::{
+    dirfd::{rename_at, Dir},
+    error::{FsOp, IoResultExt},
+    index::{Change, Journal},
+    rename::rename_noreplace,
+    trash::{create_dir_all_owned, Trash},
+    trashinfo::TrashInfo,
+};
+use sha2::{Digest, Sha256};
 use std::{
     ffi::{OsStr, OsString},
-    fs,
+    fmt::Write,
+    fs, io,
+    os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
     rc::Rc,
 };
 
+/// The length of the IDs returned by [`TrashFile::id`]
+pub const ID_LEN: usize = 10;
+
 /// A trashed file
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct TrashFile {
@@ -40,12 +53,7 @@ impl TrashFile {
     /// - the file does not have filestem
     /// - other io errors
     pub fn from_trashinfo_path(info_file_path: &Path, trash: Rc<Trash>) -> crate::Result<Self> {
-        let info_file = fs::read_to_string(info_file_path).map_err(|e| {
-            crate::Error::InvalidTrashinfoFile(
-                info_file_path.to_owned(),
-                Box::new(crate::Error::IoError(e)),
-            )
-        })?;
+        let info_file = fs::read_to_string(info_file_path).fs_err(FsOp::Read, info_file_path)?;
         Self::from_trashinfo_file(&info_file, info_file_path, trash)
             .map_err(|e| crate::Error::InvalidTrashinfoFile(info_file_path.to_owned(), Box::new(e)))
     }
@@ -68,7 +76,11 @@ impl TrashFile {
             .to_owned();
 
         if fs::symlink_metadata(trash.files_dir().join(&without_trashinfo_ext)).is_err() {
-            log::warn!("Orphaned trashinfo file: {}", info_file_path.display());
+            event!(
+                warn,
+                "Orphaned trashinfo file",
+                info_path = info_file_path.display()
+            );
             return Err(crate::Error::OrphanedTrashinfoFile);
         }
 
@@ -84,11 +96,23 @@ impl TrashFile {
     /// The location this entry will be moved to when restored
     #[must_use]
     pub fn original_path(&self) -> PathBuf {
-        if self.trashinfo.path.is_relative() {
-            self.trash.mount_root().join(&self.trashinfo.path)
-        } else {
-            self.trashinfo.path.clone()
+        self.trashinfo.original_path(&self.trash)
+    }
+
+    /// A short ID derived from the original path of this file.
+    ///
+    /// IDs can be used to identify files even if their path contains otherwise unprintable bytes.
+    /// They are [`ID_LEN`] characters long and are **not** guaranteed to be unique,
+    /// as the same path can be trashed multiple times.
+    #[must_use]
+    pub fn id(&self) -> String {
+        let hash = Sha256::digest(self.original_path().as_os_str().as_bytes());
+        let mut id = String::with_capacity(ID_LEN);
+        for b in hash.iter().take(ID_LEN.div_ceil(2)) {
+            write!(&mut id, "{:02x}", b).unwrap();
         }
+        id.truncate(ID_LEN);
+        id
     }
 
     /// The time this item was moved into the trash
@@ -96,7 +120,7 @@ impl TrashFile {
     /// The spec says that this *should* be local time, but it can't be guaranteed.
     #[must_use]
     pub fn deleted_at(&self) -> chrono::NaiveDateTime {
-        self.trashinfo.deleted_at
+        self.trashinfo.deleted_at()
     }
 
     /// Full path to this entrys entry in the files directory
@@ -114,7 +138,13 @@ impl TrashFile {
     }
 
     /// Permanently remove this file from the trash
+    #[allow(clippy::result_large_err)]
     pub fn remove(self) -> Result<(), (Self, crate::Error)> {
+        let _span = span!(
+            "remove",
+            trash = self.trash.trash_dir().display(),
+            name = Path::new(&self.raw_filename).display()
+        );
         match remove_inner(&self) {
             Ok(()) => Ok(()),
             Err(e) => Err((self, e)),
@@ -127,11 +157,45 @@ impl TrashFile {
         &self.trash
     }
 
+    pub(crate) fn trash_rc(&self) -> Rc<Trash> {
+        self.trash.clone()
+    }
+
+    pub(crate) fn raw_filename(&self) -> &OsStr {
+        &self.raw_filename
+    }
+
+    /// The parsed `.trashinfo` file of this item
+    #[must_use]
+    pub fn trashinfo(&self) -> &TrashInfo {
+        &self.trashinfo
+    }
+
+    /// All extension keys (`X-...`) of the `.trashinfo` file, in the order they are written in
+    #[must_use]
+    pub fn extra(&self) -> &[(String, String)] {
+        self.trashinfo.extra()
+    }
+
+    /// The value of the extension key `key`, if it is set
+    #[must_use]
+    pub fn get_extra(&self, key: &str) -> Option<&str> {
+        self.trashinfo.get_extra(key)
+    }
+
     /// Restores the file to it's original location, creating all parent
     /// directories of the file if they don't exist anymore.
     ///
     /// Returns the location the file was restored to.
+    #[allow(clippy::result_large_err)]
     pub fn restore(self, overwrite_existing: bool) -> Result<PathBuf, (Self, crate::Error)> {
+        let _span = span!(
+            "restore",
+            trash = self.trash.trash_dir().display(),
+            name = Path::new(&self.raw_filename).display(),
+            original_path = self.original_path().display(),
+            overwrite = overwrite_existing
+        );
         match restore_inner(&self, overwrite_existing) {
             Ok(v) => Ok(v),
             Err(e) => Err((self, e)),
@@ -151,6 +215,20 @@ impl TrashFile {
         }
     }
 
+    /// The size in bytes, if it is already known without touching the filesystem
+    #[cfg(feature = "serde")]
+    pub(crate) fn known_size(&self) -> Option<u64> {
+        #[cfg(feature = "fs_extra")]
+        return self.size;
+        #[cfg(not(feature = "fs_extra"))]
+        return None;
+    }
+
+    #[cfg(feature = "fs_extra")]
+    pub(crate) fn set_size(&mut self, size: Option<u64>) {
+        self.size = size;
+    }
+
     /// Same as size, but *uncached*
     #[cfg(feature = "fs_extra")]
     pub fn size_uncached(&self) -> Result<u64, fs_extra::error::Error> {
@@ -159,29 +237,84 @@ impl TrashFile {
 }
 
 fn remove_inner(file: &TrashFile) -> crate::Result<()> {
-    let files_file = file.files_filepath();
-    let file_meta = fs::symlink_metadata(&files_file)?;
-    if file_meta.is_dir() {
-        fs::remove_dir_all(&files_file)?;
-    } else {
-        fs::remove_file(files_file)?;
+    let dirs = file.trash.open_dirs(false)?;
+    let lock = dirs.lock()?;
+    let journal = Journal::begin(&dirs);
+
+    // The payload is moved out of the way first, so an interruption never leaves
+    // a partially deleted entry behind. The actual deletion happens afterwards.
+    let Some(staged) = dirs.stage(&file.raw_filename)? else {
+        return Err(io::Error::from(io::ErrorKind::NotFound))
+            .fs_err(FsOp::Stat, file.files_filepath());
+    };
+    let removed = dirs.remove_info(&file.raw_filename).and_then(|removed| {
+        if removed {
+            Ok(())
+        } else {
+            Err(io::Error::from(io::ErrorKind::NotFound))
+                .fs_err(FsOp::RemoveFile, file.info_filepath())
+        }
+    });
+    if let Err(e) = removed {
+        // Without removing the info file the entry isn't removed, so it gets it's payload back
+        if let Err(e) = dirs.unstage(&staged, &file.raw_filename) {
+            event!(
+                error,
+                "Failed to move staged file back",
+                name = Path::new(&staged).display(),
+                error = e
+            );
+        }
+        return Err(e);
+    }
+
+    if let Some(journal) = journal {
+        journal.commit(&dirs, &[Change::Removed(&file.raw_filename)]);
     }
-    Ok(fs::remove_file(file.info_filepath())?)
+    drop(lock);
+
+    // The entry is gone at this point, leftovers are deleted by the next removal
+    if let Err(e) = dirs.purge() {
+        event!(
+            warn,
+            "Failed to delete staged files, retrying on next removal",
+            error = e
+        );
+    }
+    Ok(())
 }
 
 fn restore_inner(file: &TrashFile, overwrite_existing: bool) -> crate::Result<PathBuf> {
+    let dirs = file.trash.open_dirs(false)?;
+    let _lock = dirs.lock()?;
+    let journal = Journal::begin(&dirs);
     let original_path = file.original_path();
 
-    if !overwrite_existing && fs::symlink_metadata(&original_path).is_ok() {
-        return Err(crate::Error::AlreadyExists(original_path));
-    }
-
     if let Some(parent) = original_path.parent() {
         assert!(parent.is_absolute());
-        fs::create_dir_all(parent)?;
+
+        // When root restores the file of another user, the payload keeps it's owner,
+        // but the recreated parent directories have to be handed over to the user
+        create_dir_all_owned(parent, 0o777, file.trash.foreign_owner())?;
     }
 
-    fs::rename(file.files_filepath(), &original_path)?;
-    fs::remove_file(file.info_filepath())?;
+    let files_file = file.files_filepath();
+    let name = Path::new(&file.raw_filename);
+    if overwrite_existing {
+        rename_at(dirs.files(), name, Dir::Cwd, &original_path)
+            .rename_err(&files_file, &original_path)?;
+    } else {
+        match rename_noreplace(dirs.files(), name, Dir::Cwd, &original_path) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(crate::Error::AlreadyExists(original_path))
+            }
+            other => other.rename_err(&files_file, &original_path)?,
+        }
+    }
+    dirs.remove_info(&file.raw_filename)?;
+
+    if let Some(journal) = journal {
+        journal.commit(&dirs, &[Change::Removed(&file.raw_filename)]);
+    }
     Ok(original_path)
 }
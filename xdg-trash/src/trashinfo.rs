@@ -1,19 +1,54 @@
-use chrono::NaiveDateTime;
+use crate::trash::Trash;
+use chrono::{Local, NaiveDateTime};
 use std::{
-    collections::HashMap, ffi::OsStr, io::Write, os::unix::ffi::OsStrExt, path::PathBuf,
+    ffi::OsStr,
+    io::Write,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
+/// The contents of a `.trashinfo` file.
+///
+/// Besides the original path and the deletion date, this keeps all extension keys
+/// (keys starting with `X-`) in the order they appear in, so they survive being parsed
+/// and written again. Other unknown keys are dropped.
+///
+/// The path is stored as written, relative paths have to be resolved
+/// against the trash they belong to (see [`TrashInfo::original_path`]).
+///
+/// # Example
+/// ```
+/// use xdg_trash::TrashInfo;
+///
+/// let info = TrashInfo::builder("/home/user/report.txt")
+///     .extra("X-Trashed-By", "cleanup-script")
+///     .build()
+///     .unwrap();
+///
+/// let parsed = TrashInfo::parse(&info.serialize()).unwrap();
+/// assert_eq!(parsed.get_extra("X-Trashed-By"), Some("cleanup-script"));
+/// ```
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-/// 1:1 representation of a .trashinfo file.
-/// The caller is responsible for handling relative paths etc.
 pub struct TrashInfo {
-    pub path: PathBuf,
-    pub deleted_at: NaiveDateTime,
+    path: PathBuf,
+    deleted_at: NaiveDateTime,
+    extra: Vec<(String, String)>,
 }
 
+/// Builds a [`TrashInfo`], see [`TrashInfo::builder`]
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct TrashInfoBuilder {
+    path: PathBuf,
+    deleted_at: Option<NaiveDateTime>,
+    extra: Vec<(String, String)>,
+}
+
+const HEADER: &str = "[Trash Info]";
 const PATH_KEY: &str = "Path";
 const DELDATE_KEY: &str = "DeletionDate";
+const EXTRA_PREFIX: &str = "X-";
 
 impl FromStr for TrashInfo {
     type Err = crate::Error;
@@ -21,54 +56,173 @@ impl FromStr for TrashInfo {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut lines = s.lines();
 
-        if lines.next() != Some("[Trash Info]") {
+        if lines.next() != Some(HEADER) {
             return Err(crate::Error::InvalidFirstLine);
         }
 
-        fn parse_line(line: &str) -> Option<(&str, &str)> {
-            let mut line = line.split('=');
-            Some((line.next()?, line.next()?))
+        let mut path = None;
+        let mut deleted_at = None;
+        let mut extra = Vec::new();
+        for line in lines.filter(|x| !x.is_empty()) {
+            let (key, value) = line.split_once('=').ok_or(crate::Error::InvalidKeyValues)?;
+            match key {
+                PATH_KEY => path = Some(value),
+                DELDATE_KEY => deleted_at = Some(value),
+                _ if key.starts_with(EXTRA_PREFIX) => insert_extra(&mut extra, key, value),
+                _ => event!(debug, "Ignoring unknown trashinfo key", key = key),
+            }
         }
 
-        let kv_lookup = lines
-            .map(parse_line)
-            .collect::<Option<HashMap<_, _>>>()
-            .ok_or(crate::Error::InvalidKeyValues)?;
-
-        let path = kv_lookup
-            .get(PATH_KEY)
-            .ok_or(crate::Error::MissingKey(PATH_KEY))?;
+        let path = path.ok_or(crate::Error::MissingKey(PATH_KEY))?;
         let path = urlencoding::decode_binary(path.as_bytes());
-        let path = OsStr::from_bytes(&path);
-        let path = PathBuf::from(path);
-
-        let deleted_at = kv_lookup
-            .get(DELDATE_KEY)
-            .ok_or(crate::Error::MissingKey(DELDATE_KEY))?;
+        let path = PathBuf::from(OsStr::from_bytes(&path));
 
+        let deleted_at = deleted_at.ok_or(crate::Error::MissingKey(DELDATE_KEY))?;
         let deleted_at = try_different_parsers(deleted_at)
             .map_err(|e| crate::Error::InvalidDateTimeNoParserMatched { errors: e })?;
 
-        Ok(Self { path, deleted_at })
+        Ok(Self {
+            path,
+            deleted_at,
+            extra,
+        })
     }
 }
 
 impl TrashInfo {
-    fn create_trashinfofile(&self) -> String {
-        let encoded = urlencoding::encode_binary(self.path.as_os_str().as_bytes());
-        format!(
-            "[Trash Info]\nPath={}\nDeletionDate={}",
-            encoded,
+    /// Starts building a trashinfo for `path`, deleted now and without extension keys
+    pub fn builder(path: impl Into<PathBuf>) -> TrashInfoBuilder {
+        TrashInfoBuilder {
+            path: path.into(),
+            deleted_at: None,
+            extra: Vec::new(),
+        }
+    }
+
+    /// Parses the contents of a `.trashinfo` file, same as [`str::parse`]
+    pub fn parse(s: &str) -> crate::Result<Self> {
+        s.parse()
+    }
+
+    /// The path as written in the file, which may be relative
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The time the file was moved into the trash
+    #[must_use]
+    pub fn deleted_at(&self) -> NaiveDateTime {
+        self.deleted_at
+    }
+
+    /// All extension keys and their values, in the order they are written in
+    #[must_use]
+    pub fn extra(&self) -> &[(String, String)] {
+        &self.extra
+    }
+
+    /// The value of the extension key `key`, if it is set
+    #[must_use]
+    pub fn get_extra(&self, key: &str) -> Option<&str> {
+        self.extra
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Resolves a possibly relative path against the mount root of the trash
+    pub fn original_path(&self, trash: &Trash) -> PathBuf {
+        if self.path.is_relative() {
+            trash.mount_root().join(&self.path)
+        } else {
+            self.path.clone()
+        }
+    }
+
+    /// Returns the contents of the `.trashinfo` file.
+    ///
+    /// The path is percent-encoded the same way GIO and KDE do, extension keys follow the
+    /// deletion date in their original order.
+    #[must_use]
+    pub fn serialize(&self) -> String {
+        let mut file = format!(
+            "{HEADER}\n{PATH_KEY}={}\n{DELDATE_KEY}={}\n",
+            encode_path(self.path.as_os_str().as_bytes()),
             // The same format that nautilus and dolphin use. The spec claims rfc3339, but that doesn't work out at all...
             self.deleted_at.format("%Y-%m-%dT%H:%M:%S")
-        )
+        );
+        for (key, value) in &self.extra {
+            file.push_str(&format!("{key}={value}\n"));
+        }
+        file
+    }
+
+    /// Writes the contents of the `.trashinfo` file to `w`, see [`TrashInfo::serialize`]
+    pub fn write_to(&self, mut w: impl Write) -> std::io::Result<()> {
+        w.write_all(self.serialize().as_bytes())
+    }
+}
+
+impl TrashInfoBuilder {
+    /// Sets the deletion date, defaults to the current local time
+    pub fn deleted_at(mut self, deleted_at: NaiveDateTime) -> Self {
+        self.deleted_at = Some(deleted_at);
+        self
+    }
+
+    /// Sets the extension key `key` to `value`, replacing an earlier value.
+    ///
+    /// Keys must start with `X-`, this is checked by [`TrashInfoBuilder::build`].
+    pub fn extra(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        insert_extra(&mut self.extra, &key.into(), &value.into());
+        self
+    }
+
+    /// Builds the trashinfo, failing if an extension key or value is invalid
+    pub fn build(self) -> crate::Result<TrashInfo> {
+        for (key, value) in &self.extra {
+            if !key.starts_with(EXTRA_PREFIX) || key.contains(['=', '\n', '\r']) {
+                return Err(crate::Error::InvalidExtraKey(key.clone()));
+            }
+            if value.contains(['\n', '\r']) {
+                return Err(crate::Error::InvalidExtraValue(key.clone()));
+            }
+        }
+
+        Ok(TrashInfo {
+            path: self.path,
+            deleted_at: self
+                .deleted_at
+                .unwrap_or_else(|| Local::now().naive_local()),
+            extra: self.extra,
+        })
+    }
+}
+
+fn insert_extra(extra: &mut Vec<(String, String)>, key: &str, value: &str) {
+    match extra.iter_mut().find(|(k, _)| k == key) {
+        Some((_, v)) => value.clone_into(v),
+        None => extra.push((key.to_owned(), value.to_owned())),
     }
+}
 
-    pub fn write_to(&self, mut w: impl Write) -> crate::Result<()> {
-        let file = self.create_trashinfofile();
-        w.write_all(file.as_bytes())
-            .map_err(crate::Error::IoError)
+/// Percent-encodes a path the same way GIO, KDE and python trash-cli do:
+/// everything except unreserved characters (RFC 3986) and `/` is escaped, using uppercase hex digits.
+fn encode_path(path: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut encoded = String::with_capacity(path.len());
+    for &byte in path {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push('%');
+            encoded.push(HEX[usize::from(byte >> 4)] as char);
+            encoded.push(HEX[usize::from(byte & 0xF)] as char);
+        }
     }
+    encoded
 }
 
 fn try_different_parsers(input: &str) -> Result<NaiveDateTime, Vec<chrono::ParseError>> {
@@ -102,11 +256,11 @@ fn try_different_parsers(input: &str) -> Result<NaiveDateTime, Vec<chrono::Parse
             }
             Err(e) => {
                 errs.push(e);
-                log::trace!("Datetime parser failed: {e}");
+                event!(trace, "Datetime parser failed", input = input, error = e);
             }
         }
     }
 
-    log::debug!("No datetime parsers matched");
+    event!(debug, "No datetime parsers matched", input = input);
     Err(errs)
 }
@@ -0,0 +1,131 @@
+use std::{
+    env,
+    ffi::{CStr, CString, OsStr},
+    io, mem,
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
+    ptr,
+    sync::Mutex,
+};
+
+/// `getpwent` iterates over global state, so only one enumeration can run at a time
+static PASSWD_LOCK: Mutex<()> = Mutex::new(());
+
+/// A user account from the passwd database, used to find the trashes of other users
+/// (see [`crate::UnifiedTrash::for_users`])
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct User {
+    /// The login name
+    pub name: String,
+    /// The user id
+    pub uid: u32,
+    /// The id of the users primary group
+    pub gid: u32,
+    /// The home directory
+    pub home_dir: PathBuf,
+}
+
+impl User {
+    /// Returns every user in the passwd database.
+    ///
+    /// Other code in the same process using `getpwent` while this runs will interfere with it.
+    pub fn all() -> crate::Result<Vec<Self>> {
+        let _guard = PASSWD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut users = Vec::new();
+        let result = unsafe {
+            libc::setpwent();
+            loop {
+                // `getpwent` returns null both at the end and on errors, only `errno` tells them apart
+                *libc::__errno_location() = 0;
+                let pw = libc::getpwent();
+                if !pw.is_null() {
+                    users.push(Self::from_passwd(&*pw));
+                    continue;
+                }
+                let e = io::Error::last_os_error();
+                match e.raw_os_error() {
+                    // Some implementations set one of these at the end of the database
+                    Some(0 | libc::ENOENT | libc::ESRCH) => break Ok(users),
+                    _ => break Err(crate::Error::UserLookup(e)),
+                }
+            }
+        };
+        unsafe { libc::endpwent() };
+        result
+    }
+
+    /// Returns the user that ran this program through `sudo` or `pkexec`, if running as root.
+    ///
+    /// The user is taken from `SUDO_UID` (or `SUDO_USER`) and `PKEXEC_UID`.
+    /// Returns `None` if not running as root, or if root invoked the program itself.
+    pub fn invoking() -> crate::Result<Option<Self>> {
+        if unsafe { libc::geteuid() } != 0 {
+            return Ok(None);
+        }
+
+        let uid = ["SUDO_UID", "PKEXEC_UID"]
+            .into_iter()
+            .find_map(|x| env::var(x).ok()?.parse::<u32>().ok());
+        let user = match (uid, env::var("SUDO_USER")) {
+            (Some(uid), _) => Self::from_uid(uid)?,
+            (None, Ok(name)) => Some(Self::from_name(&name)?),
+            (None, Err(_)) => None,
+        };
+        Ok(user.filter(|x| x.uid != 0))
+    }
+
+    /// Looks up the user with the login name `name`
+    pub fn from_name(name: &str) -> crate::Result<Self> {
+        let c_name = CString::new(name).map_err(|_| crate::Error::UnknownUser(name.to_owned()))?;
+        lookup(|pwd, buf, len, result| unsafe {
+            libc::getpwnam_r(c_name.as_ptr(), pwd, buf, len, result)
+        })?
+        .ok_or_else(|| crate::Error::UnknownUser(name.to_owned()))
+    }
+
+    /// Looks up the user with the id `uid`, `None` if there is no such user
+    pub fn from_uid(uid: u32) -> crate::Result<Option<Self>> {
+        lookup(|pwd, buf, len, result| unsafe { libc::getpwuid_r(uid, pwd, buf, len, result) })
+    }
+
+    /// # Safety
+    /// The strings in `pw` must be valid
+    unsafe fn from_passwd(pw: &libc::passwd) -> Self {
+        let name = CStr::from_ptr(pw.pw_name).to_string_lossy().into_owned();
+        let home_dir = if pw.pw_dir.is_null() {
+            PathBuf::new()
+        } else {
+            PathBuf::from(OsStr::from_bytes(CStr::from_ptr(pw.pw_dir).to_bytes()))
+        };
+        Self {
+            name,
+            uid: pw.pw_uid,
+            gid: pw.pw_gid,
+            home_dir,
+        }
+    }
+}
+
+/// Calls one of the reentrant `getpw*_r` functions, growing the buffer until the entry fits
+fn lookup(
+    f: impl Fn(
+        *mut libc::passwd,
+        *mut libc::c_char,
+        libc::size_t,
+        *mut *mut libc::passwd,
+    ) -> libc::c_int,
+) -> crate::Result<Option<User>> {
+    let mut buf: Vec<libc::c_char> = vec![0; 1024];
+    loop {
+        let mut pwd: libc::passwd = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+        match f(&mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) {
+            0 if result.is_null() => return Ok(None),
+            0 => return Ok(Some(unsafe { User::from_passwd(&pwd) })),
+            libc::ERANGE => buf.resize(buf.len() * 2, 0),
+            // Some implementations report a missing user as one of these instead of a null result
+            libc::ENOENT | libc::ESRCH | libc::EBADF | libc::EPERM => return Ok(None),
+            e => return Err(crate::Error::UserLookup(io::Error::from_raw_os_error(e))),
+        }
+    }
+}